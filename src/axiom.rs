@@ -2,13 +2,15 @@ use std::{fmt, str::FromStr};
 use regex::Regex;
 use crate::ascesis_parser::{
     CesFileParser, CesFileBlockParser, ImmediateDefParser, CesImmediateParser, CesInstanceParser,
-    PropBlockParser, CapsBlockParser, UnboundedBlockParser, WeightsBlockParser, InhibitBlockParser,
-    WeightlessBlockParser, RexParser, ThinArrowRuleParser, FatArrowRuleParser, PolynomialParser,
+    PolyDefParser, PropBlockParser, CapsBlockParser, UnboundedBlockParser, WeightsBlockParser,
+    InhibitBlockParser, WeightlessBlockParser, PriorityBlockParser, RexParser,
+    ThinArrowRuleParser, FatArrowRuleParser, PolynomialParser,
 };
 use crate::{
-    CesFile, CesFileBlock, ImmediateDef, CesImmediate, CesInstance, PropBlock, CapacitiesBlock,
-    UnboundedBlock, WeightsBlock, InhibitorsBlock, WeightlessBlock, Rex, ThinArrowRule,
-    FatArrowRule, Polynomial, Lexer, AscesisError, AscesisErrorKind, error::ParserError,
+    CesFile, CesFileBlock, ImmediateDef, CesImmediate, CesInstance, PolyDef, PropBlock,
+    CapacitiesBlock, UnboundedBlock, WeightsBlock, InhibitorsBlock, WeightlessBlock, PriorityBlock,
+    Rex, ThinArrowRule, FatArrowRule, Polynomial, Lexer, AscesisError, AscesisErrorKind,
+    error::ParserError,
 };
 
 #[derive(Clone, Debug)]
@@ -19,10 +21,10 @@ impl Axiom {
         let symbol = symbol.as_ref();
 
         match symbol {
-            "CesFileBlock" | "ImmediateDef" | "CesImmediate" | "CesInstance" | "PropBlock"
-            | "CapsBlock" | "UnboundedBlock" | "WeightsBlock" | "InhibitBlock"
-            | "ActivateBlock" | "DropBlock" | "Rex" | "ThinArrowRule" | "FatArrowRule"
-            | "Polynomial" => Some(Axiom(symbol.to_owned())),
+            "CesFileBlock" | "ImmediateDef" | "CesImmediate" | "CesInstance" | "PolyDef"
+            | "PropBlock" | "CapsBlock" | "UnboundedBlock" | "WeightsBlock" | "InhibitBlock"
+            | "ActivateBlock" | "DropBlock" | "PriorityBlock" | "Rex" | "ThinArrowRule"
+            | "FatArrowRule" | "Polynomial" => Some(Axiom(symbol.to_owned())),
             _ => None,
         }
     }
@@ -30,6 +32,7 @@ impl Axiom {
     pub fn guess_from_phrase<S: AsRef<str>>(phrase: S) -> Self {
         lazy_static! {
             static ref IMM_RE: Regex = Regex::new(r"^ces\s+[[:alpha:]][[:word:]]*\s*\{").unwrap();
+            static ref POLY_RE: Regex = Regex::new(r"^poly\s+[[:alpha:]][[:word:]]*\s*=").unwrap();
             static ref VIS_RE: Regex = Regex::new(r"^vis\s*\{").unwrap();
             static ref SAT_RE: Regex = Regex::new(r"^sat\s*\{").unwrap();
             static ref CAPS_RE: Regex = Regex::new(r"^caps\s*\{").unwrap();
@@ -38,6 +41,7 @@ impl Axiom {
             static ref INHIBIT_RE: Regex = Regex::new(r"^inhibit\s*\{").unwrap();
             static ref ACTIVATE_RE: Regex = Regex::new(r"^activate\s*\{").unwrap();
             static ref DROP_RE: Regex = Regex::new(r"^drop\s*\{").unwrap();
+            static ref PRIORITY_RE: Regex = Regex::new(r"^priority\s*\{").unwrap();
             static ref TIN_RE: Regex = Regex::new(r"^[[:alpha:]][[:word:]]*\s*!\s*\(").unwrap();
             static ref IIN_RE: Regex =
                 Regex::new(r"^[[:alpha:]][[:word:]]*\s*\(\s*\)\s*$").unwrap();
@@ -50,6 +54,8 @@ impl Axiom {
 
         if IMM_RE.is_match(phrase) {
             Axiom("ImmediateDef".to_owned())
+        } else if POLY_RE.is_match(phrase) {
+            Axiom("PolyDef".to_owned())
         } else if VIS_RE.is_match(phrase) || SAT_RE.is_match(phrase) {
             Axiom("PropBlock".to_owned())
         } else if CAPS_RE.is_match(phrase) {
@@ -64,6 +70,8 @@ impl Axiom {
             Axiom("ActivateBlock".to_owned())
         } else if DROP_RE.is_match(phrase) {
             Axiom("DropBlock".to_owned())
+        } else if PRIORITY_RE.is_match(phrase) {
+            Axiom("PriorityBlock".to_owned())
         } else if IIN_RE.is_match(phrase) {
             Axiom("CesImmediate".to_owned())
         } else if TIN_RE.is_match(phrase) {
@@ -100,6 +108,7 @@ impl Axiom {
             "ImmediateDef" => from_phrase_as!(ImmediateDef, phrase),
             "CesImmediate" => from_phrase_as!(CesImmediate, phrase),
             "CesInstance" => from_phrase_as!(CesInstance, phrase),
+            "PolyDef" => from_phrase_as!(PolyDef, phrase),
             "PropBlock" => from_phrase_as!(PropBlock, phrase),
             "CapsBlock" => from_phrase_as!(CapacitiesBlock, phrase),
             "UnboundedBlock" => from_phrase_as!(UnboundedBlock, phrase),
@@ -107,6 +116,7 @@ impl Axiom {
             "InhibitBlock" => from_phrase_as!(InhibitorsBlock, phrase),
             "ActivateBlock" => from_phrase_as!(WeightlessBlock, phrase),
             "DropBlock" => from_phrase_as!(WeightlessBlock, phrase),
+            "PriorityBlock" => from_phrase_as!(PriorityBlock, phrase),
             "Rex" => from_phrase_as!(Rex, phrase),
             "ThinArrowRule" => from_phrase_as!(ThinArrowRule, phrase),
             "FatArrowRule" => from_phrase_as!(FatArrowRule, phrase),
@@ -116,6 +126,15 @@ impl Axiom {
     }
 }
 
+/// Parsing of a phrase derived from a known nonterminal.
+///
+/// A phrase is accepted only if parsing succeeds without recovering
+/// from any error.  In particular, a phrase containing an erroneous
+/// block is rejected, instead of being parsed into a
+/// [`CesFileBlock::Bad`].  All recovered errors are reported, as a
+/// single [`AscesisErrorKind::ParsingRecovery`], which is also the
+/// behavior of [`CesFile::from_script`].  This applies to the
+/// [`FromStr`] implementations of all parsable types, too.
 pub trait FromPhrase: fmt::Debug {
     fn from_phrase<S>(phrase: S) -> Result<Self, ParserError>
     where
@@ -135,7 +154,13 @@ macro_rules! impl_from_phrase_for {
                     err.map_token(|t| format!("{}", t)).map_error(|e| e.to_owned())
                 })?;
 
-                Ok(result)
+                if errors.is_empty() {
+                    Ok(result)
+                } else {
+                    let error = AscesisErrorKind::from(errors).with_script(phrase);
+
+                    Err(ParserError::User { error })
+                }
             }
         }
     };
@@ -146,12 +171,14 @@ impl_from_phrase_for!(CesFileBlock, CesFileBlockParser);
 impl_from_phrase_for!(ImmediateDef, ImmediateDefParser);
 impl_from_phrase_for!(CesImmediate, CesImmediateParser);
 impl_from_phrase_for!(CesInstance, CesInstanceParser);
+impl_from_phrase_for!(PolyDef, PolyDefParser);
 impl_from_phrase_for!(PropBlock, PropBlockParser);
 impl_from_phrase_for!(CapacitiesBlock, CapsBlockParser);
 impl_from_phrase_for!(UnboundedBlock, UnboundedBlockParser);
 impl_from_phrase_for!(WeightsBlock, WeightsBlockParser);
 impl_from_phrase_for!(InhibitorsBlock, InhibitBlockParser);
 impl_from_phrase_for!(WeightlessBlock, WeightlessBlockParser);
+impl_from_phrase_for!(PriorityBlock, PriorityBlockParser);
 impl_from_phrase_for!(Rex, RexParser);
 impl_from_phrase_for!(ThinArrowRule, ThinArrowRuleParser);
 impl_from_phrase_for!(FatArrowRule, FatArrowRuleParser);
@@ -174,13 +201,75 @@ impl_from_str_for!(CesFileBlock);
 impl_from_str_for!(ImmediateDef);
 impl_from_str_for!(CesImmediate);
 impl_from_str_for!(CesInstance);
+impl_from_str_for!(PolyDef);
 impl_from_str_for!(PropBlock);
 impl_from_str_for!(CapacitiesBlock);
 impl_from_str_for!(UnboundedBlock);
 impl_from_str_for!(WeightsBlock);
 impl_from_str_for!(InhibitorsBlock);
 impl_from_str_for!(WeightlessBlock);
+impl_from_str_for!(PriorityBlock);
 impl_from_str_for!(Rex);
 impl_from_str_for!(ThinArrowRule);
 impl_from_str_for!(FatArrowRule);
 impl_from_str_for!(Polynomial);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str_rejects_recovered() {
+        let script = "ces A { a -> b }\nces B { -> }\nces C { ]] }";
+        let err = script.parse::<CesFile>().unwrap_err().to_string();
+
+        assert_eq!(err, CesFile::from_script(script).unwrap_err().to_string());
+        assert!(err.starts_with("Unrecognized token `->` found at [2:9]:[2:11]"));
+        assert!(err.contains("\nerror: Unrecognized token `]` found at [3:9]:[3:10]"));
+
+        assert!("ces B { -> }".parse::<CesFileBlock>().is_err());
+        assert!("ces B { a -> b }".parse::<CesFileBlock>().is_ok());
+    }
+
+    #[test]
+    fn test_from_str_empty_argument() {
+        let err = "x!(a,,b,)".parse::<CesInstance>().unwrap_err();
+
+        assert_eq!(err.to_string(), "Empty argument #2 of instance 'x' at [1:6]");
+        assert_eq!("x!(a, b,)".parse::<CesInstance>().unwrap().to_string(), "x!(a, b)");
+    }
+
+    fn assert_displayed<T>(phrase: &str, expected: &str)
+    where
+        T: FromStr<Err = ParserError> + fmt::Display + PartialEq + fmt::Debug,
+    {
+        let block: T = phrase.parse().unwrap();
+
+        assert_eq!(block.to_string(), expected);
+        assert_eq!(expected.parse::<T>().unwrap(), block);
+    }
+
+    #[test]
+    fn test_block_display() {
+        assert_displayed::<PropBlock>(
+            "vis { title: \"Main\", labels: { b: \"B\", a: \"A\" }, layout: [1 2, 3] }",
+            "vis { labels: { a: \"A\", b: \"B\" }, layout: [ 1 2, 3 ], title: \"Main\" }",
+        );
+        assert_displayed::<CapacitiesBlock>("caps { 3 c b, ω a, }", "caps { ω a, 3 b, 3 c }");
+        assert_displayed::<UnboundedBlock>("unbounded { b a }", "unbounded { a b }");
+        assert_displayed::<WeightsBlock>(
+            "weights { 3 a -> c b, 2 b <- a }",
+            "weights { 2 b <- a, 3 a -> b c }",
+        );
+        assert_displayed::<InhibitorsBlock>(
+            "inhibit { a -> b + c d, e <- f }",
+            "inhibit { e <- f, a -> b, a -> c d }",
+        );
+        assert_displayed::<WeightlessBlock>("drop { a <- b + c }", "drop { a <- b, a <- c }");
+        assert_displayed::<WeightlessBlock>("activate { a -> b }", "activate { a -> b }");
+        assert_displayed::<PriorityBlock>(
+            "priority { A > B C > D }",
+            "priority { A > B, A > C, B > D, C > D }",
+        );
+    }
+}
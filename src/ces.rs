@@ -1,12 +1,15 @@
-use std::{ops::Deref, fmt, error::Error};
+use std::{collections::BTreeSet, ops::Deref, fmt, error::Error};
 use log::Level::Debug;
+use lalrpop_util::{ErrorRecovery, ParseError};
 use aces::{
     Content, PartialContent, Compilable, CompilableMut, CompilableAsContent,
     CompilableAsDependency, ContextHandle, DotId, Polarity, sat,
 };
 use crate::{
     PropBlock, PropSelector, CapacitiesBlock, UnboundedBlock, WeightsBlock, InhibitorsBlock,
-    WeightlessBlock, Rex, Lexer, AscesisError, AscesisErrorKind, ascesis_parser::CesFileParser,
+    WeightlessBlock, PriorityBlock, Rex, ThinArrowRule, RexPattern, DotName, PolyDef, SharingStats,
+    Priorities, Layout, Provenance, Lexer, Span, AscesisError, AscesisErrorKind,
+    polynomial::PolyAbbreviations, ascesis_parser::CesFileParser, error::RawParserRecovery,
 };
 
 #[derive(Default, Debug)]
@@ -16,6 +19,8 @@ pub struct CesFile {
     root_block_id: Option<usize>,
     root_content:  Option<PartialContent>,
     modules:       Vec<PartialContent>,
+    sharing:       Option<SharingStats>,
+    priorities:    Option<Priorities>,
 }
 
 impl CesFile {
@@ -80,7 +85,11 @@ impl CesFile {
         }
     }
 
-    fn get_root(&self) -> Result<&ImmediateDef, AscesisError> {
+    /// Returns the definition of the root structure, once
+    /// [`set_root_name`] succeeds.
+    ///
+    /// [`set_root_name`]: CesFile::set_root_name
+    pub fn get_root(&self) -> Result<&ImmediateDef, AscesisError> {
         if let Some(ndx) = self.root_block_id {
             if let CesFileBlock::Imm(ref root) = self.blocks[ndx] {
                 Ok(root)
@@ -197,12 +206,311 @@ impl CesFile {
 
         Ok(None)
     }
+
+    /// Expands polynomial abbreviations in all structure
+    /// definitions.
+    fn resolve_abbreviations(&mut self) -> Result<(), AscesisError> {
+        let abbrevs = PolyAbbreviations::resolve(self.blocks.iter().filter_map(|block| {
+            if let CesFileBlock::Poly(def) = block {
+                Some(def)
+            } else {
+                None
+            }
+        }))?;
+
+        if !abbrevs.is_empty() {
+            for block in self.blocks.iter_mut() {
+                if let CesFileBlock::Imm(ref mut imm) = block {
+                    imm.rex.expand_abbreviations(&abbrevs)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that no local binding is named after a structure
+    /// defined in this file or already compiled into the context.
+    ///
+    /// References to such a binding would be indistinguishable from
+    /// instantiations of the structure.
+    fn check_bindings(&self, ctx: &ContextHandle) -> Result<(), AscesisError> {
+        let defined: BTreeSet<&CesName> = self
+            .blocks
+            .iter()
+            .filter_map(|block| {
+                if let CesFileBlock::Imm(imm) = block {
+                    Some(&imm.name)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let ctx = ctx.lock().unwrap();
+
+        for block in self.blocks.iter() {
+            if let CesFileBlock::Imm(imm) = block {
+                for name in imm.bindings.iter() {
+                    if defined.contains(name) || ctx.has_content(name) {
+                        return Err(AscesisErrorKind::BindingShadowsStructure(
+                            (**name).clone(),
+                            (*imm.name).clone(),
+                        )
+                        .into())
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Merges all priority blocks into a single relation and checks
+    /// it against names of known structures.
+    fn resolve_priorities(&mut self, ctx: &ContextHandle) -> Result<(), AscesisError> {
+        let priorities = Priorities::resolve(self.blocks.iter().filter_map(|block| {
+            if let CesFileBlock::Priority(blk) = block {
+                Some(blk)
+            } else {
+                None
+            }
+        }))?;
+
+        let defined: BTreeSet<&CesName> = self
+            .blocks
+            .iter()
+            .filter_map(|block| {
+                if let CesFileBlock::Imm(imm) = block {
+                    Some(&imm.name)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        {
+            let ctx = ctx.lock().unwrap();
+
+            for name in priorities.get_names() {
+                if !defined.contains(name) && !ctx.has_content(name) {
+                    return Err(AscesisErrorKind::PriorityUnknown((**name).clone()).into())
+                }
+            }
+        }
+
+        self.priorities = Some(priorities);
+
+        Ok(())
+    }
+
+    /// Returns the priority relation resolved during the last
+    /// compilation, or `None` if this file wasn't compiled yet.
+    #[inline]
+    pub fn get_priorities(&self) -> Option<&Priorities> {
+        self.priorities.as_ref()
+    }
+
+    /// Selects those of the `enabled` items, each given by its dots,
+    /// which take precedence according to the priority blocks of
+    /// this file, see [`Priorities::select`].
+    ///
+    /// A simulation of the compiled structure should call this
+    /// whenever several firings are enabled at once.  All items are
+    /// selected if this file wasn't compiled yet.
+    pub fn select_enabled<I, T>(&self, enabled: I) -> Vec<T>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[DotId]>,
+    {
+        if let Some(ref priorities) = self.priorities {
+            priorities.select(enabled)
+        } else {
+            enabled.into_iter().collect()
+        }
+    }
+
+    #[inline]
+    pub(crate) fn get_blocks(&self) -> &[CesFileBlock] {
+        &self.blocks
+    }
+
+    /// Returns node sharing statistics gathered during the last
+    /// compilation, or `None` if this file wasn't compiled yet.
+    #[inline]
+    pub fn get_sharing_stats(&self) -> Option<&SharingStats> {
+        self.sharing.as_ref()
+    }
+
+    /// Computes layout hints for the compiled root structure, unless
+    /// any `vis` block of this file specifies a `layout` already, in
+    /// which case `None` is returned.
+    ///
+    /// The result displays as a `vis` block, hence it may be written
+    /// back into the script.  See also [`CesFile::add_layout_hints`].
+    pub fn get_layout_hints(
+        &mut self,
+        ctx: &ContextHandle,
+    ) -> Result<Option<Layout>, AscesisError> {
+        for block in self.blocks.iter() {
+            if let CesFileBlock::Vis(blk) = block {
+                if blk.get("layout").is_some() {
+                    return Ok(None)
+                }
+            }
+        }
+
+        self.get_content()?;
+
+        Ok(Some(Layout::from_content(ctx, self)))
+    }
+
+    /// Computes layout hints, as in [`CesFile::get_layout_hints`],
+    /// and appends them to this file as a `vis` block.
+    ///
+    /// Returns `true` if a block was added, or `false` if this file
+    /// specifies a `layout` already, or if there is nothing to lay
+    /// out.  The script this file was created from isn't modified.
+    pub fn add_layout_hints(&mut self, ctx: &ContextHandle) -> Result<bool, AscesisError> {
+        match self.get_layout_hints(ctx)? {
+            Some(layout) if !layout.is_empty() => {
+                self.blocks.push(CesFileBlock::Vis(layout.to_prop_block()));
+
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Searches all structure definitions for thin arrow rules
+    /// matching a `pattern`, see [`Rex::find`].
+    ///
+    /// Returns matching rules together with names of structures
+    /// defining them, in order of definition.
+    pub fn find<S: AsRef<str>>(
+        &self,
+        pattern: S,
+    ) -> Result<Vec<(&CesName, ThinArrowRule)>, AscesisError> {
+        let pattern: RexPattern = pattern.as_ref().parse()?;
+        let mut result = Vec::new();
+
+        for block in self.blocks.iter() {
+            if let CesFileBlock::Imm(imm) = block {
+                let tars = imm.rex.find_matching(&pattern);
+
+                result.extend(tars.into_iter().map(|tar| (&imm.name, tar)));
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Searches all structure definitions for instantiations of the
+    /// structure named `ces_name`, see [`Rex::find_instances_of`].
+    ///
+    /// Returns [`Span`]s of instantiations together with names of
+    /// structures containing them, in order of definition.
+    pub fn find_instances_of<S: AsRef<str>>(&self, ces_name: S) -> Vec<(&CesName, Span)> {
+        let ces_name = ces_name.as_ref();
+        let mut result = Vec::new();
+
+        for block in self.blocks.iter() {
+            if let CesFileBlock::Imm(imm) = block {
+                let spans = imm.rex.find_instances_of(ces_name);
+
+                result.extend(spans.into_iter().map(|span| (&imm.name, span)));
+            }
+        }
+
+        result
+    }
+
+    /// Reports every construct, which contributed an entry between
+    /// dots `cause` and `effect` to the compiled root structure.
+    ///
+    /// Constructs are searched for in the root's definition and,
+    /// recursively, in definitions of all structures instantiated
+    /// there, in order of occurrence.  Structures compiled into the
+    /// context from other files aren't inspected.
+    pub fn explain<S, T>(&self, cause: S, effect: T) -> Result<Vec<Provenance>, AscesisError>
+    where
+        S: AsRef<str>,
+        T: AsRef<str>,
+    {
+        // Abbreviations are resolved during compilation.
+        self.get_content()?;
+
+        let root = self.get_root()?;
+        let cause = DotName::from(cause.as_ref().to_owned());
+        let effect = DotName::from(effect.as_ref().to_owned());
+        let mut chain = vec![(root.name.clone(), Span::default())];
+        let mut result = Vec::new();
+
+        self.explain_definition(root, &cause, &effect, &mut chain, &mut result);
+
+        Ok(result)
+    }
+
+    fn explain_definition(
+        &self,
+        imm: &ImmediateDef,
+        cause: &DotName,
+        effect: &DotName,
+        chain: &mut Vec<(CesName, Span)>,
+        result: &mut Vec<Provenance>,
+    ) {
+        imm.rex.explain_into(cause, effect, chain, result);
+
+        for (name, span) in imm.rex.get_instantiations() {
+            if chain.iter().any(|(n, _)| *n == name) {
+                continue
+            }
+
+            for block in self.blocks.iter() {
+                if let CesFileBlock::Imm(other) = block {
+                    if other.name == name {
+                        chain.push((name.clone(), span));
+                        self.explain_definition(other, cause, effect, chain, result);
+                        chain.pop();
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl CompilableMut for CesFile {
     fn compile_mut(&mut self, ctx: &ContextHandle) -> Result<bool, Box<dyn Error>> {
         info!("Start compiling...");
 
+        // Resolve all definitions, before their dot names are
+        // inspected.
+
+        self.resolve_abbreviations()?;
+        self.check_bindings(ctx)?;
+
+        // Before anything is shared into the context, check for
+        // collisions with dots already known to the context.
+
+        let sharing = SharingStats::gather(
+            ctx,
+            self.blocks.iter().filter_map(|block| {
+                if let CesFileBlock::Imm(imm) = block {
+                    Some(imm)
+                } else {
+                    None
+                }
+            }),
+        );
+
+        sharing.log_collisions();
+        self.sharing = Some(sharing);
+
+        // Priorities may refer to structures defined in this file or
+        // already compiled into the context.
+
+        self.resolve_priorities(ctx)?;
+
         // First pass: compile all property blocks.
 
         for block in self.blocks.iter().rev() {
@@ -239,7 +547,10 @@ impl CompilableMut for CesFile {
                 CesFileBlock::Drop(ref drop) => {
                     drop.compile(ctx)?;
                 }
-                CesFileBlock::SAT(_) | CesFileBlock::Vis(_) => {}
+                CesFileBlock::Poly(_)
+                | CesFileBlock::Priority(_)
+                | CesFileBlock::SAT(_)
+                | CesFileBlock::Vis(_) => {}
                 CesFileBlock::Bad(err) => {
                     println!("{:?}", err);
                 }
@@ -265,6 +576,10 @@ impl CompilableMut for CesFile {
             }
         }
 
+        if let Some(ref mut priorities) = self.priorities {
+            priorities.gather_footprints(ctx);
+        }
+
         let root = self.get_root()?;
 
         if root.is_compiled(ctx) {
@@ -323,6 +638,7 @@ impl Content for CesFile {
 #[derive(Debug)]
 pub enum CesFileBlock {
     Imm(ImmediateDef),
+    Poly(PolyDef),
     Vis(PropBlock),
     SAT(PropBlock),
     Caps(CapacitiesBlock),
@@ -331,6 +647,7 @@ pub enum CesFileBlock {
     Inhibit(InhibitorsBlock),
     Activate(WeightlessBlock),
     Drop(WeightlessBlock),
+    Priority(PriorityBlock),
     Bad(AscesisError),
 }
 
@@ -341,6 +658,13 @@ impl From<ImmediateDef> for CesFileBlock {
     }
 }
 
+impl From<PolyDef> for CesFileBlock {
+    #[inline]
+    fn from(def: PolyDef) -> Self {
+        CesFileBlock::Poly(def)
+    }
+}
+
 impl From<PropBlock> for CesFileBlock {
     fn from(props: PropBlock) -> Self {
         match props.get_selector() {
@@ -394,6 +718,13 @@ impl From<WeightlessBlock> for CesFileBlock {
     }
 }
 
+impl From<PriorityBlock> for CesFileBlock {
+    #[inline]
+    fn from(priority: PriorityBlock) -> Self {
+        CesFileBlock::Priority(priority)
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
 pub struct CesName(String);
 
@@ -440,14 +771,40 @@ impl<S: AsRef<str>> ToCesName for S {
 
 #[derive(Clone, Debug)]
 pub struct ImmediateDef {
-    name: CesName,
-    rex:  Rex,
+    name:     CesName,
+    rex:      Rex,
+    bindings: Vec<CesName>,
 }
 
 impl ImmediateDef {
     pub fn new(name: CesName, rex: Rex) -> Self {
         debug!("ImmediateDef of '{}': {:?}", name, rex);
-        ImmediateDef { name, rex }
+        ImmediateDef { name, rex, bindings: Vec::new() }
+    }
+
+    /// Resolves references to local `bindings` in the rule expression
+    /// of this definition, see [`Rex::with_bindings`].
+    pub(crate) fn with_bindings(mut self, bindings: Vec<(CesName, Rex)>) -> Self {
+        self.bindings = bindings.iter().map(|(name, _)| name.clone()).collect();
+        self.rex = self.rex.with_bindings(bindings);
+        self
+    }
+
+    #[inline]
+    pub fn get_name(&self) -> &CesName {
+        &self.name
+    }
+
+    #[inline]
+    pub fn get_rex(&self) -> &Rex {
+        &self.rex
+    }
+
+    /// Returns the set of all [`DotName`]s occurring in arrow rules
+    /// of this definition (its node footprint).
+    #[inline]
+    pub fn get_dot_names(&self) -> BTreeSet<DotName> {
+        self.rex.get_dot_names()
     }
 
     pub(crate) fn is_compiled(&self, ctx: &ContextHandle) -> bool {
@@ -508,31 +865,127 @@ impl CompilableAsDependency for ImmediateDef {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct CesImmediate {
     pub(crate) name: CesName,
+    pub(crate) span: Span,
 }
 
 impl CesImmediate {
     pub(crate) fn new(name: CesName) -> Self {
-        CesImmediate { name }
+        CesImmediate { name, span: Span::default() }
+    }
+
+    pub(crate) fn with_span(mut self, start: usize, end: usize) -> Self {
+        self.span = Span::new(start, end);
+        self
     }
+
+    pub fn get_name(&self) -> &CesName {
+        &self.name
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+}
+
+impl PartialEq for CesImmediate {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for CesImmediate {}
+
+impl fmt::Display for CesImmediate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}()", self.name)
+    }
+}
+
+/// A comma-separated slot of instance arguments.
+#[derive(Debug)]
+pub(crate) struct ArgSlot {
+    args: Vec<String>,
+    span: Span,
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+impl ArgSlot {
+    pub(crate) fn new(start: usize, end: usize, args: Vec<String>) -> Self {
+        ArgSlot { args, span: Span::new(start, end) }
+    }
+}
+
+#[derive(Clone, Default, Debug)]
 pub struct CesInstance {
     pub(crate) name: CesName,
     pub(crate) args: Vec<String>,
+    pub(crate) span: Span,
 }
 
 impl CesInstance {
     pub(crate) fn new(name: CesName) -> Self {
         debug!("CesInstance of '{}'", name);
-        CesInstance { name, args: Vec::new() }
+        CesInstance { name, args: Vec::new(), span: Span::default() }
+    }
+
+    /// Appends arguments gathered from comma-separated slots.
+    ///
+    /// The last slot may be empty, which allows for a trailing comma,
+    /// or for no arguments at all.  Any other empty slot is reported
+    /// in `errors` and otherwise skipped, so that parsing may go on.
+    pub(crate) fn with_arg_slots(
+        mut self,
+        head: ArgSlot,
+        tail: Vec<ArgSlot>,
+        errors: &mut Vec<RawParserRecovery>,
+    ) -> Self {
+        let num_slots = tail.len() + 1;
+
+        for (ndx, mut slot) in Some(head).into_iter().chain(tail).enumerate() {
+            if !slot.args.is_empty() {
+                self.args.append(&mut slot.args);
+            } else if ndx + 1 < num_slots {
+                let name = (*self.name).clone();
+                let error = AscesisErrorKind::EmptyArgument(name, ndx + 1, slot.span).into();
+
+                errors.push(ErrorRecovery {
+                    error:          ParseError::User { error },
+                    dropped_tokens: Vec::new(),
+                });
+            }
+        }
+
+        self
     }
 
-    pub(crate) fn with_args(mut self, mut args: Vec<String>) -> Self {
-        self.args.append(&mut args);
+    pub(crate) fn with_span(mut self, start: usize, end: usize) -> Self {
+        self.span = Span::new(start, end);
         self
     }
+
+    pub fn get_name(&self) -> &CesName {
+        &self.name
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+}
+
+impl PartialEq for CesInstance {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.args == other.args
+    }
+}
+
+impl Eq for CesInstance {}
+
+impl fmt::Display for CesInstance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}!({})", self.name, self.args.join(", "))
+    }
 }
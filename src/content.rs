@@ -43,7 +43,7 @@ impl ContentFormat for AscesisFormat {
 
         if let Some(word) = words.next() {
             match word {
-                "ces" => true,
+                "ces" | "poly" => true,
                 _ => {
                     if word.contains('{') {
                         // Script starts with a word containing left brace.
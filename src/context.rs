@@ -1,6 +1,8 @@
 use std::{collections::BTreeMap, convert::TryInto, cmp, fmt, error::Error};
 use aces::{ContextHandle, Compilable, Polarity, Capacity, Weight, sat};
-use crate::{Polynomial, DotName, DotList, Literal, AscesisError, AscesisErrorKind};
+use crate::{
+    CesName, ToCesName, Polynomial, DotName, DotList, Literal, AscesisError, AscesisErrorKind,
+};
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub enum PropSelector {
@@ -86,6 +88,35 @@ impl From<Vec<PropValue>> for PropValue {
     }
 }
 
+impl fmt::Display for PropValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PropValue::Literal(lit) => lit.fmt(f),
+            PropValue::Identifier(identifier) => identifier.fmt(f),
+            PropValue::SizeList(lits) => {
+                let lits: Vec<String> = lits.iter().map(|lit| lit.to_string()).collect();
+
+                lits.join(" ").fmt(f)
+            }
+            PropValue::IdentifierList(identifiers) => identifiers.join(" ").fmt(f),
+            PropValue::DotList(dot_list) => dot_list.fmt(f),
+            PropValue::Array(values) => {
+                write!(f, "[")?;
+
+                for (ndx, value) in values.iter().enumerate() {
+                    if ndx > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, " {}", value)?;
+                }
+
+                write!(f, " ]")
+            }
+            PropValue::Block(block) => block.fmt(f),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Default, Debug)]
 pub struct PropBlock {
     selector: PropSelector,
@@ -339,6 +370,31 @@ impl PropBlock {
     }
 }
 
+/// A block is displayed in the form it is parsed from, e.g. `vis {
+/// title: "Main", labels: { a: "Start" } }`.  Fields are ordered by
+/// key, and a nested block is displayed without a selector.
+impl fmt::Display for PropBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.selector {
+            PropSelector::AnonymousBlock => {}
+            PropSelector::Vis => write!(f, "vis ")?,
+            PropSelector::SAT => write!(f, "sat ")?,
+            PropSelector::Invalid(ref name) => write!(f, "{} ", name)?,
+        }
+
+        write!(f, "{{")?;
+
+        for (ndx, (key, value)) in self.fields.iter().enumerate() {
+            if ndx > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, " {}: {}", key, value)?;
+        }
+
+        write!(f, " }}")
+    }
+}
+
 impl Compilable for PropBlock {
     fn compile(&self, ctx: &ContextHandle) -> Result<bool, Box<dyn Error>> {
         match self.get_selector()? {
@@ -388,10 +444,23 @@ impl Compilable for PropBlock {
     }
 }
 
+/// Returns the capacity given by a `size` literal.
+fn capacity_from_literal(size: &Literal) -> Result<Capacity, AscesisError> {
+    match size {
+        Literal::Size(sz) => Capacity::finite(*sz)
+            .ok_or_else(|| AscesisError::from(AscesisErrorKind::SizeLiteralOverflow)),
+        Literal::Omega => Ok(Capacity::omega()),
+        _ => Err(AscesisError::from(AscesisErrorKind::ExpectedSizeLiteral)),
+    }
+}
+
 /// A map from dots to their capacities.
+///
+/// Capacities are kept in their literal form, so that a block may be
+/// displayed the way it is parsed.
 #[derive(Clone, PartialEq, Eq, Default, Debug)]
 pub struct CapacitiesBlock {
-    capacities: BTreeMap<DotName, Capacity>,
+    capacities: BTreeMap<DotName, Literal>,
 }
 
 impl CapacitiesBlock {
@@ -405,16 +474,12 @@ impl CapacitiesBlock {
         size: Literal,
         dot_names: Polynomial,
     ) -> Result<Self, AscesisError> {
-        let capacity = match size {
-            Literal::Size(sz) => Capacity::finite(sz)
-                .ok_or_else(|| AscesisError::from(AscesisErrorKind::SizeLiteralOverflow))?,
-            Literal::Omega => Capacity::omega(),
-            _ => return Err(AscesisError::from(AscesisErrorKind::ExpectedSizeLiteral)),
-        };
+        capacity_from_literal(&size)?;
+
         let dot_list: DotList = dot_names.try_into()?;
 
         for dot_name in dot_list.dot_names.into_iter() {
-            self.capacities.insert(dot_name, capacity);
+            self.capacities.insert(dot_name, size.clone());
         }
 
         Ok(self)
@@ -428,12 +493,29 @@ impl CapacitiesBlock {
     }
 }
 
+/// A block is displayed with a separate field for each dot, ordered
+/// by dot name, e.g. `caps { 2 a, ω b }`.
+impl fmt::Display for CapacitiesBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "caps {{")?;
+
+        for (ndx, (dot_name, size)) in self.capacities.iter().enumerate() {
+            if ndx > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, " {} {}", size, dot_name)?;
+        }
+
+        write!(f, " }}")
+    }
+}
+
 impl Compilable for CapacitiesBlock {
     fn compile(&self, ctx: &ContextHandle) -> Result<bool, Box<dyn Error>> {
         let mut ctx = ctx.lock().unwrap();
 
-        for (dot_name, cap) in self.capacities.iter() {
-            ctx.set_capacity_by_name(dot_name.as_ref(), *cap);
+        for (dot_name, size) in self.capacities.iter() {
+            ctx.set_capacity_by_name(dot_name.as_ref(), capacity_from_literal(size)?);
         }
 
         Ok(true)
@@ -459,6 +541,16 @@ impl UnboundedBlock {
     }
 }
 
+impl fmt::Display for UnboundedBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.dot_names.is_empty() {
+            write!(f, "unbounded {{ }}")
+        } else {
+            write!(f, "unbounded {{ {} }}", DotList::from(self.dot_names.clone()))
+        }
+    }
+}
+
 impl Compilable for UnboundedBlock {
     fn compile(&self, ctx: &ContextHandle) -> Result<bool, Box<dyn Error>> {
         let mut ctx = ctx.lock().unwrap();
@@ -489,7 +581,8 @@ impl WeightsBlock {
         post_dots: Polynomial,
         pre_arms: Polynomial,
     ) -> Result<Self, AscesisError> {
-        let weight = size.try_into()?;
+        let _: Weight = size.clone().try_into()?;
+        let weight = size;
         let post_dots: DotList = post_dots.try_into()?;
         let pre_arms: DotList = pre_arms.try_into()?;
 
@@ -497,6 +590,8 @@ impl WeightsBlock {
             .dot_names
             .into_iter()
             .map(|tip_name| {
+                let weight = weight.clone();
+
                 XferMultiplicity::Rx(RxWeight { weight, tip_name, pre_arms: pre_arms.clone() })
             })
             .collect();
@@ -510,7 +605,8 @@ impl WeightsBlock {
         pre_dots: Polynomial,
         post_arms: Polynomial,
     ) -> Result<Self, AscesisError> {
-        let weight = size.try_into()?;
+        let _: Weight = size.clone().try_into()?;
+        let weight = size;
         let pre_dots: DotList = pre_dots.try_into()?;
         let post_arms: DotList = post_arms.try_into()?;
 
@@ -518,6 +614,8 @@ impl WeightsBlock {
             .dot_names
             .into_iter()
             .map(|tip_name| {
+                let weight = weight.clone();
+
                 XferMultiplicity::Tx(TxWeight { weight, tip_name, post_arms: post_arms.clone() })
             })
             .collect();
@@ -539,6 +637,31 @@ impl WeightsBlock {
     }
 }
 
+/// A block is displayed with a separate field for each transfer
+/// multiplicity, e.g. `weights { 2 b <- a, 3 a -> b c }`.
+impl fmt::Display for WeightsBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "weights {{")?;
+
+        for (ndx, weight) in self.xfer_multiplicities.iter().enumerate() {
+            if ndx > 0 {
+                write!(f, ",")?;
+            }
+
+            match weight {
+                XferMultiplicity::Rx(rx) => {
+                    write!(f, " {} {} <- {}", rx.weight, rx.tip_name, rx.pre_arms)?
+                }
+                XferMultiplicity::Tx(tx) => {
+                    write!(f, " {} {} -> {}", tx.weight, tx.tip_name, tx.post_arms)?
+                }
+            }
+        }
+
+        write!(f, " }}")
+    }
+}
+
 impl Compilable for WeightsBlock {
     fn compile(&self, ctx: &ContextHandle) -> Result<bool, Box<dyn Error>> {
         let mut ctx = ctx.lock().unwrap();
@@ -552,7 +675,7 @@ impl Compilable for WeightsBlock {
                         Polarity::Rx,
                         rx.tip_name.as_ref(),
                         pre_names,
-                        rx.weight,
+                        rx.weight.clone().try_into()?,
                     );
                 }
                 XferMultiplicity::Tx(tx) => {
@@ -562,7 +685,7 @@ impl Compilable for WeightsBlock {
                         Polarity::Tx,
                         tx.tip_name.as_ref(),
                         post_names,
-                        tx.weight,
+                        tx.weight.clone().try_into()?,
                     );
                 }
             }
@@ -601,7 +724,7 @@ impl cmp::PartialOrd for XferMultiplicity {
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 struct RxWeight {
-    weight:   Weight,
+    weight:   Literal,
     tip_name: DotName,
     pre_arms: DotList,
 }
@@ -626,7 +749,7 @@ impl cmp::PartialOrd for RxWeight {
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 struct TxWeight {
-    weight:    Weight,
+    weight:    Literal,
     tip_name:  DotName,
     post_arms: DotList,
 }
@@ -710,6 +833,25 @@ impl InhibitorsBlock {
     }
 }
 
+impl fmt::Display for InhibitorsBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "inhibit {{")?;
+
+        for (ndx, inhibitor) in self.inhibitors.iter().enumerate() {
+            if ndx > 0 {
+                write!(f, ",")?;
+            }
+
+            match inhibitor {
+                Inhibitor::Rx(rx) => write!(f, " {} <- {}", rx.post_tip, rx.pre_arms)?,
+                Inhibitor::Tx(tx) => write!(f, " {} -> {}", tx.pre_tip, tx.post_arms)?,
+            }
+        }
+
+        write!(f, " }}")
+    }
+}
+
 impl Compilable for InhibitorsBlock {
     fn compile(&self, ctx: &ContextHandle) -> Result<bool, Box<dyn Error>> {
         let mut ctx = ctx.lock().unwrap();
@@ -909,6 +1051,31 @@ impl From<WeightlessBlock> for WeightsBlock {
     }
 }
 
+/// A block of [`Polarity::Rx`] is displayed as a `drop` block, any
+/// other as an `activate` block.
+impl fmt::Display for WeightlessBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.polarity == Some(Polarity::Rx) {
+            write!(f, "drop {{")?;
+        } else {
+            write!(f, "activate {{")?;
+        }
+
+        for (ndx, split) in self.splits.iter().enumerate() {
+            if ndx > 0 {
+                write!(f, ",")?;
+            }
+
+            match split {
+                Weightless::Activate(tx) => write!(f, " {} -> {}", tx.pre_tip, tx.post_arms)?,
+                Weightless::Drop(rx) => write!(f, " {} <- {}", rx.post_tip, rx.pre_arms)?,
+            }
+        }
+
+        write!(f, " }}")
+    }
+}
+
 impl Compilable for WeightlessBlock {
     fn compile(&self, ctx: &ContextHandle) -> Result<bool, Box<dyn Error>> {
         let mut ctx = ctx.lock().unwrap();
@@ -1000,3 +1167,77 @@ impl cmp::PartialOrd for RxWeightless {
         Some(self.cmp(other))
     }
 }
+
+/// A list of declared priorities between structures, each a pair of
+/// names of a higher-priority structure and a lower-priority one.
+///
+/// Pairs are alphabetically ordered and deduplicated.  Transitivity
+/// and acyclicity of the declared relation are resolved only by
+/// [`Priorities::resolve`].
+///
+/// [`Priorities::resolve`]: crate::Priorities::resolve
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct PriorityBlock {
+    pairs: Vec<(CesName, CesName)>,
+}
+
+impl PriorityBlock {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates a block from a chain of name lists, where every name
+    /// in a list takes precedence over all names in the next list.
+    pub(crate) fn from_chain(head: Vec<String>, tail: Vec<Vec<String>>) -> Self {
+        let mut pairs = Vec::new();
+        let mut higher = head;
+
+        for lower in tail.into_iter() {
+            for high in higher.iter() {
+                for low in lower.iter() {
+                    pairs.push((high.to_ces_name(), low.to_ces_name()));
+                }
+            }
+            higher = lower;
+        }
+
+        PriorityBlock::new().with_pairs(pairs)
+    }
+
+    fn with_pairs(mut self, mut pairs: Vec<(CesName, CesName)>) -> Self {
+        self.pairs.append(&mut pairs);
+        self.pairs.sort();
+        let len = self.pairs.partition_dedup().0.len();
+        self.pairs.truncate(len);
+        self
+    }
+
+    pub(crate) fn with_more(mut self, more: Vec<Self>) -> Self {
+        for block in more {
+            self = self.with_pairs(block.pairs);
+        }
+        self
+    }
+
+    /// Returns an iterator over declared pairs of the form `(higher,
+    /// lower)`.
+    pub fn iter_pairs(&self) -> std::slice::Iter<'_, (CesName, CesName)> {
+        self.pairs.iter()
+    }
+}
+
+impl fmt::Display for PriorityBlock {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "priority {{")?;
+
+        for (ndx, (higher, lower)) in self.pairs.iter().enumerate() {
+            if ndx > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, " {} > {}", higher, lower)?;
+        }
+
+        write!(f, " }}")
+    }
+}
@@ -0,0 +1,191 @@
+//! Differential testing of [`Rex`] compilation pipelines.
+//!
+//! The same parsed rule expression is compiled through a reference
+//! pipeline and through a candidate pipeline, and both results are
+//! compared in the serialized form used by the [`golden`] module.
+//! When results differ, the harness descends into operands of the
+//! diverging expression, for as long as any of them diverges, and
+//! reports the innermost diverging subexpression.
+//!
+//! [`golden`]: crate::golden
+
+use std::{fmt, error::Error};
+use aces::{ContextHandle, PartialContent, CompilableAsContent};
+use crate::{Rex, golden};
+
+/// A way of compiling a [`Rex`] into content.
+pub trait RexPipeline {
+    /// Returns the name identifying this pipeline in reports.
+    fn get_name(&self) -> &str;
+
+    /// Compiles `rex` in the context `ctx`, which already holds
+    /// contents of all structures instantiated in `rex`.
+    fn compile_rex(&self, rex: &Rex, ctx: &ContextHandle) -> Result<PartialContent, Box<dyn Error>>;
+}
+
+/// The pipeline used in [`CesFile`] compilation: FIT transformation
+/// followed by bottom-up merging of operand contents.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ReferencePipeline;
+
+impl RexPipeline for ReferencePipeline {
+    #[inline]
+    fn get_name(&self) -> &str {
+        "reference"
+    }
+
+    #[inline]
+    fn compile_rex(
+        &self,
+        rex: &Rex,
+        ctx: &ContextHandle,
+    ) -> Result<PartialContent, Box<dyn Error>> {
+        rex.get_compiled_content(ctx)
+    }
+}
+
+/// The innermost subexpression, for which two pipelines produce
+/// different results.
+#[derive(Clone, Debug)]
+pub struct Divergence {
+    rex:            Rex,
+    reference_name: String,
+    candidate_name: String,
+    expected:       String,
+    actual:         String,
+}
+
+impl Divergence {
+    #[inline]
+    pub fn get_rex(&self) -> &Rex {
+        &self.rex
+    }
+
+    /// Returns the serialized result of the reference pipeline.
+    #[inline]
+    pub fn get_expected(&self) -> &str {
+        &self.expected
+    }
+
+    /// Returns the serialized result of the candidate pipeline.
+    #[inline]
+    pub fn get_actual(&self) -> &str {
+        &self.actual
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Pipeline '{}' diverges from '{}' at `{}` (- {}, + {}):\n{}",
+            self.candidate_name,
+            self.reference_name,
+            self.rex,
+            self.reference_name,
+            self.candidate_name,
+            golden::diff(&self.expected, &self.actual).unwrap_or_default()
+        )
+    }
+}
+
+impl Error for Divergence {}
+
+fn serialize_outcome<P>(pipeline: &P, rex: &Rex, ctx: &ContextHandle) -> String
+where
+    P: RexPipeline + ?Sized,
+{
+    match pipeline.compile_rex(rex, ctx) {
+        Ok(mut content) => golden::serialize(ctx, &mut content),
+        Err(err) => format!("error: {}\n", err),
+    }
+}
+
+/// Compiles `rex` in the context `ctx` through both pipelines and
+/// compares the results.
+///
+/// A failure to compile counts as a result, hence two pipelines
+/// failing in the same way are considered equivalent.
+pub fn compare<A, B>(
+    reference: &A,
+    candidate: &B,
+    rex: &Rex,
+    ctx: &ContextHandle,
+) -> Result<(), Divergence>
+where
+    A: RexPipeline + ?Sized,
+    B: RexPipeline + ?Sized,
+{
+    let mut rex = rex.clone();
+    let mut expected = serialize_outcome(reference, &rex, ctx);
+    let mut actual = serialize_outcome(candidate, &rex, ctx);
+
+    if expected == actual {
+        return Ok(())
+    }
+
+    'descend: loop {
+        let children = if rex.kinds.is_empty() { Vec::new() } else { rex.get_children(0).to_vec() };
+
+        for child in children {
+            let subtree = rex.get_subtree(child);
+            let sub_expected = serialize_outcome(reference, &subtree, ctx);
+            let sub_actual = serialize_outcome(candidate, &subtree, ctx);
+
+            if sub_expected != sub_actual {
+                rex = subtree;
+                expected = sub_expected;
+                actual = sub_actual;
+
+                continue 'descend
+            }
+        }
+
+        break
+    }
+
+    Err(Divergence {
+        rex,
+        reference_name: reference.get_name().to_owned(),
+        candidate_name: candidate.get_name().to_owned(),
+        expected,
+        actual,
+    })
+}
+
+/// Compiles `script` by [`golden::compile_script`] and compares the
+/// root's rule expression through both pipelines.
+///
+/// Structures instantiated by the root are compiled the usual way,
+/// i.e. only the root's rule expression goes through the candidate
+/// pipeline.
+pub fn compare_script<A, B, S, R>(
+    reference: &A,
+    candidate: &B,
+    script: S,
+    root_name: R,
+) -> Result<(), Box<dyn Error>>
+where
+    A: RexPipeline + ?Sized,
+    B: RexPipeline + ?Sized,
+    S: AsRef<str>,
+    R: AsRef<str>,
+{
+    let (ctx, ces_file) = golden::compile_script(script, root_name)?;
+
+    compare(reference, candidate, ces_file.get_root()?.get_rex(), &ctx)?;
+
+    Ok(())
+}
+
+/// Asserts that both pipelines agree on the root of `script`,
+/// panicking with the [`Divergence`] report otherwise.
+pub fn assert_equivalent<A, B, S, R>(reference: &A, candidate: &B, script: S, root_name: R)
+where
+    A: RexPipeline + ?Sized,
+    B: RexPipeline + ?Sized,
+    S: AsRef<str>,
+    R: AsRef<str>,
+{
+    compare_script(reference, candidate, script, root_name).unwrap_or_else(|err| panic!("{}", err))
+}
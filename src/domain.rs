@@ -1,4 +1,4 @@
-use std::{collections::BTreeSet, convert::TryFrom, iter::FromIterator};
+use std::{collections::BTreeSet, convert::TryFrom, iter::FromIterator, fmt};
 use crate::{Polynomial, AscesisError, AscesisErrorKind};
 
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Default, Debug)]
@@ -16,6 +16,12 @@ impl AsRef<str> for DotName {
     }
 }
 
+impl fmt::Display for DotName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
 pub trait ToDotName {
     fn to_dot(&self) -> DotName;
 }
@@ -49,6 +55,22 @@ impl DotList {
     }
 }
 
+impl fmt::Display for DotList {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut dot_names = self.dot_names.iter();
+
+        if let Some(dot) = dot_names.next() {
+            dot.fmt(f)?;
+
+            for dot in dot_names {
+                write!(f, " {}", dot)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl From<DotName> for DotList {
     fn from(dot: DotName) -> Self {
         DotList { dot_names: vec![dot] }
@@ -1,5 +1,5 @@
 use std::{fmt, num::ParseIntError, error::Error};
-use crate::{PropSelector, Token};
+use crate::{PropSelector, Span, Token};
 
 pub(crate) type ParserError = lalrpop_util::ParseError<usize, String, AscesisError>;
 pub(crate) type RawParserError<'input> =
@@ -31,7 +31,7 @@ fn format_location(mut pos: usize, script: &str) -> String {
     "<outside>".into()
 }
 
-fn format_span(span: &logos::Span, script: &str) -> String {
+pub(crate) fn format_span(span: &logos::Span, script: &str) -> String {
     let mut start_location = None;
     let mut end_location = None;
     let mut pos = span.start;
@@ -84,7 +84,9 @@ fn display_parsing_recovery(
 ) -> fmt::Result {
     for (num, err) in errors.iter().enumerate() {
         let message = if let Some(script) = script {
-            format!("{}", err.clone().map_location(|pos| format_location(pos, script)))
+            let err = err.clone().map_error(|e| e.or_with_script(script));
+
+            format!("{}", err.map_location(|pos| format_location(pos, script)))
         } else {
             format!("{}", err)
         };
@@ -115,6 +117,15 @@ fn display_lexing_failure(
     write!(f, "Invalid token \"{}\" at {}", token, format_span(span, script))
 }
 
+fn display_empty_argument(
+    name: &str,
+    slot: usize,
+    location: &dyn fmt::Display,
+    f: &mut fmt::Formatter,
+) -> fmt::Result {
+    write!(f, "Empty argument #{} of instance '{}' at {}", slot, name, location)
+}
+
 #[derive(Clone, Debug)]
 pub enum AscesisErrorKind {
     ParsingRecovery(Vec<ParserError>),
@@ -127,7 +138,19 @@ pub enum AscesisErrorKind {
     RootBlockMismatch,
     RootBlockMissing,
     RootUnresolvable,
+    PolyRedefined(String),
+    PolyCycle(Vec<String>),
+    PolyNotADotList(String),
+    PolyRepeated(String),
+    PriorityCycle(Vec<String>),
+    PriorityUnknown(String),
+    EmptyArgument(String, usize, Span),
+    BindingShadowsStructure(String, String),
     ScriptUncompiled,
+    ScriptMissing,
+    ModelUnknown(String),
+    ModelVersionUnknown(String, u32),
+    RegistryCorrupted(String),
     UnexpectedDependency(String),
     InvalidAST,
     FatLeak,
@@ -143,6 +166,7 @@ pub enum AscesisErrorKind {
     ParseIntFailure(ParseIntError),
     EnquoteFailure(String),
     NotADotList,
+    InvalidBinOp(String),
 }
 
 impl fmt::Display for AscesisErrorKind {
@@ -160,7 +184,33 @@ impl fmt::Display for AscesisErrorKind {
             RootBlockMismatch => write!(f, "Root block mismatch"),
             RootBlockMissing => write!(f, "Root block missing"),
             RootUnresolvable => write!(f, "Root contains instances without known definitions"),
+            PolyRedefined(name) => write!(f, "Redefined polynomial abbreviation '{}'", name),
+            PolyCycle(names) => {
+                write!(f, "Cyclic polynomial abbreviation '{}'", names.join("' -> '"))
+            }
+            PolyNotADotList(name) => {
+                write!(f, "Polynomial abbreviation '{}' used as a dot list", name)
+            }
+            PolyRepeated(name) => {
+                write!(f, "Polynomial abbreviation '{}' repeated in a product", name)
+            }
+            PriorityCycle(names) => write!(f, "Cyclic priority '{}'", names.join("' > '")),
+            PriorityUnknown(name) => write!(f, "Priority of unknown structure '{}'", name),
+            EmptyArgument(name, slot, span) => display_empty_argument(name, *slot, span, f),
+            BindingShadowsStructure(name, ces_name) => {
+                write!(
+                    f,
+                    "Let binding '{}' in '{}' shadows a structure of the same name",
+                    name, ces_name
+                )
+            }
             ScriptUncompiled => write!(f, "Script uncompiled"),
+            ScriptMissing => write!(f, "Content not created from a script"),
+            ModelUnknown(name) => write!(f, "Unknown model '{}'", name),
+            ModelVersionUnknown(name, version) => {
+                write!(f, "Unknown version {} of model '{}'", version, name)
+            }
+            RegistryCorrupted(path) => write!(f, "Corrupted registry index {}", path),
             UnexpectedDependency(name) => write!(f, "Unexpected uncompiled dependency '{}'", name),
             InvalidAST => write!(f, "Invalid AST"),
             FatLeak => write!(f, "Fat arrow rule leaked through FIT transformation"),
@@ -180,6 +230,7 @@ impl fmt::Display for AscesisErrorKind {
             ParseIntFailure(err) => err.fmt(f),
             EnquoteFailure(err) => write!(f, "{}", err),
             NotADotList => write!(f, "Not a dot list"),
+            InvalidBinOp(symbol) => write!(f, "Invalid binary operator '{}'", symbol),
         }
     }
 }
@@ -222,6 +273,17 @@ pub struct AscesisError {
     kind:   AscesisErrorKind,
 }
 
+impl AscesisError {
+    /// Attaches `script` to this error, unless some script is
+    /// attached already.
+    pub(crate) fn or_with_script<S: AsRef<str>>(mut self, script: S) -> Self {
+        if self.script.is_none() {
+            self.script = Some(script.as_ref().to_owned());
+        }
+        self
+    }
+}
+
 impl From<AscesisErrorKind> for AscesisError {
     #[inline]
     fn from(kind: AscesisErrorKind) -> Self {
@@ -239,6 +301,9 @@ impl fmt::Display for AscesisError {
                 LexingFailure(ref token, ref span) => {
                     display_lexing_failure(token.as_str(), span, script, f)
                 }
+                EmptyArgument(ref name, slot, ref span) => {
+                    display_empty_argument(name, slot, &span.to_location(script), f)
+                }
                 ref kind => kind.fmt(f),
             }
         } else {
@@ -0,0 +1,221 @@
+//! Snapshot (golden) testing of compiled content.
+//!
+//! Compiled content is serialized into a deterministic, line-oriented
+//! textual form and compared against a snapshot stored in a file.
+//! Each line describes a single dot, in the form `name <- causes ->
+//! effects`, where either part is omitted if empty.  Lines are
+//! ordered by dot name, monomials of a polynomial are ordered
+//! alphabetically, and so are dot names in a monomial.
+//!
+//! Setting the environment variable named by [`REGEN_VAR`] to a
+//! nonempty value switches to the regeneration mode, where snapshots
+//! are overwritten with actual results instead of being compared.
+
+use std::{env, fs, path::Path, error::Error};
+use aces::{Context, ContextHandle, Content, CompilableMut, DotId};
+use crate::CesFile;
+
+/// Name of the environment variable enabling the regeneration mode.
+pub const REGEN_VAR: &str = "ASCESIS_GOLDEN_REGEN";
+
+/// Returns `true` if snapshots should be regenerated.
+pub fn is_regenerating() -> bool {
+    match env::var_os(REGEN_VAR) {
+        Some(value) => !value.is_empty(),
+        None => false,
+    }
+}
+
+fn serialize_polynomial(ctx: &Context, poly: &[Vec<DotId>]) -> String {
+    let mut monomials: Vec<String> = poly
+        .iter()
+        .map(|mono| {
+            let mut names: Vec<String> = mono
+                .iter()
+                .map(|&id| match ctx.get_dot_name(id) {
+                    Some(name) => name.to_owned(),
+                    None => format!("#{:?}", id),
+                })
+                .collect();
+
+            names.sort();
+            names.dedup();
+            names.join(" ")
+        })
+        .collect();
+
+    monomials.sort();
+    monomials.dedup();
+    monomials.join(" + ")
+}
+
+/// Serializes `content` into the snapshot form, resolving dot names
+/// in `ctx`.
+pub fn serialize<C: Content + ?Sized>(ctx: &ContextHandle, content: &mut C) -> String {
+    let ids = content.get_carrier_ids();
+    let ctx = ctx.lock().unwrap();
+    let mut lines = Vec::new();
+
+    for id in ids {
+        let mut line = match ctx.get_dot_name(id) {
+            Some(name) => name.to_owned(),
+            None => format!("#{:?}", id),
+        };
+
+        if let Some(causes) = content.get_causes_by_id(id) {
+            if !causes.is_empty() {
+                line.push_str(" <- ");
+                line.push_str(&serialize_polynomial(&ctx, causes));
+            }
+        }
+
+        if let Some(effects) = content.get_effects_by_id(id) {
+            if !effects.is_empty() {
+                line.push_str(" -> ");
+                line.push_str(&serialize_polynomial(&ctx, effects));
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines.sort();
+
+    let mut result = lines.join("\n");
+    result.push('\n');
+    result
+}
+
+/// Compiles `script` in a fresh context, starting from the structure
+/// named `root_name`.
+///
+/// Returns the context along with the compiled file, so that the
+/// result may be inspected further, e.g. serialized or explained.
+pub fn compile_script<S, R>(
+    script: S,
+    root_name: R,
+) -> Result<(ContextHandle, CesFile), Box<dyn Error>>
+where
+    S: AsRef<str>,
+    R: AsRef<str>,
+{
+    let ctx = Context::new_toplevel("golden");
+    let mut ces_file = CesFile::from_script(script)?;
+
+    ces_file.set_root_name(root_name)?;
+    ces_file.compile_mut(&ctx)?;
+
+    Ok((ctx, ces_file))
+}
+
+/// Serializes the result of [`compile_script`] into the snapshot
+/// form.
+pub fn serialize_script<S, R>(script: S, root_name: R) -> Result<String, Box<dyn Error>>
+where
+    S: AsRef<str>,
+    R: AsRef<str>,
+{
+    let (ctx, mut ces_file) = compile_script(script, root_name)?;
+
+    Ok(serialize(&ctx, &mut ces_file))
+}
+
+/// Returns a line diff of `expected` and `actual` text, or `None` if
+/// both are equal.
+///
+/// Lines present only in `expected` are prefixed with `-`, lines
+/// present only in `actual` are prefixed with `+`, common lines are
+/// indented.
+pub fn diff(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None
+    }
+
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    // Lengths of longest common subsequences of all suffix pairs.
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = String::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            result.push_str(&format!("  {}\n", old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            result.push_str(&format!("- {}\n", old[i]));
+            i += 1;
+        } else {
+            result.push_str(&format!("+ {}\n", new[j]));
+            j += 1;
+        }
+    }
+
+    if result.lines().all(|line| line.starts_with("  ")) {
+        // Texts differ in line terminators only.
+        result.push_str("(difference in line terminators)\n");
+    }
+
+    Some(result)
+}
+
+/// Compares `actual` against the snapshot stored at `path`.
+///
+/// Returns an error carrying a readable diff, if the snapshot
+/// differs, or if it is missing.  In the regeneration mode, the
+/// snapshot is overwritten with `actual` instead.
+pub fn check_snapshot<P: AsRef<Path>>(path: P, actual: &str) -> Result<(), Box<dyn Error>> {
+    let path = path.as_ref();
+
+    if is_regenerating() {
+        fs::write(path, actual)?;
+        info!("Regenerated snapshot {}", path.display());
+
+        return Ok(())
+    }
+
+    let expected = match fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(err) => {
+            return Err(format!(
+                "Missing snapshot {} ({}), rerun with {}=1 to create it",
+                path.display(),
+                err,
+                REGEN_VAR
+            )
+            .into())
+        }
+    };
+
+    if let Some(diff) = diff(&expected, actual) {
+        Err(format!(
+            "Snapshot {} mismatch (- expected, + actual):\n{}Rerun with {}=1 to accept changes",
+            path.display(),
+            diff,
+            REGEN_VAR
+        )
+        .into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Panicking version of [`check_snapshot`], for use in tests.
+pub fn assert_snapshot<P: AsRef<Path>>(path: P, actual: &str) {
+    if let Err(err) = check_snapshot(path, actual) {
+        panic!("{}", err)
+    }
+}
@@ -0,0 +1,368 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    cmp::Ordering,
+    fmt,
+};
+use aces::{Content, ContextHandle};
+use crate::{PropBlock, PropValue, Literal};
+
+// Number of barycenter sweeps, each consisting of a downward and an
+// upward pass.
+const NUM_SWEEPS: usize = 4;
+
+/// Layout hints for visualization of compiled content.
+///
+/// Dots are assigned to layers along the causal order, so that each
+/// arrow points from a lower layer to a higher one, except for arrows
+/// closing a cycle.  Within a layer, dots are ranked so as to reduce
+/// the number of crossing arrows.  This is the layered (Sugiyama)
+/// approach, simplified by omitting dummy nodes of long arrows:
+///
+/// 1. cycles are broken by reversing back arrows of a depth-first
+///    search;
+///
+/// 2. each dot is assigned to a layer by the longest path from a
+///    source;
+///
+/// 3. dots of each layer are ordered by the barycenter heuristic,
+///    in several downward and upward passes.
+///
+/// All steps are deterministic: ties are broken by dot names.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Layout {
+    // Maps dot names to (layer, rank) pairs.
+    positions:  BTreeMap<String, (usize, usize)>,
+    num_layers: usize,
+}
+
+impl Layout {
+    /// Lays out `content`, resolving dot names in `ctx`.
+    ///
+    /// Dots without a name in `ctx` can't be referred to in a `vis`
+    /// block, hence they are skipped, along with their arrows.
+    pub fn from_content<C: Content + ?Sized>(ctx: &ContextHandle, content: &mut C) -> Self {
+        let ids = content.get_carrier_ids();
+        let ctx = ctx.lock().unwrap();
+        let name_of = |id| ctx.get_dot_name(id).map(|name| name.to_owned());
+
+        let mut names = BTreeSet::new();
+        let mut arcs = BTreeSet::new();
+
+        for id in ids {
+            let name = match name_of(id) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            if let Some(effects) = content.get_effects_by_id(id) {
+                for other in effects.iter().flatten().filter_map(|&other| name_of(other)) {
+                    arcs.insert((name.clone(), other));
+                }
+            }
+
+            if let Some(causes) = content.get_causes_by_id(id) {
+                for other in causes.iter().flatten().filter_map(|&other| name_of(other)) {
+                    arcs.insert((other, name.clone()));
+                }
+            }
+
+            names.insert(name);
+        }
+
+        Self::from_graph(names, arcs)
+    }
+
+    /// Lays out a graph given by its `arcs`, i.e. pairs of names.
+    pub fn from_arcs<I, S>(arcs: I) -> Self
+    where
+        I: IntoIterator<Item = (S, S)>,
+        S: AsRef<str>,
+    {
+        let arcs: BTreeSet<(String, String)> = arcs
+            .into_iter()
+            .map(|(from, to)| (from.as_ref().to_owned(), to.as_ref().to_owned()))
+            .collect();
+
+        Self::from_graph(BTreeSet::new(), arcs)
+    }
+
+    fn from_graph(mut names: BTreeSet<String>, arcs: BTreeSet<(String, String)>) -> Self {
+        for (from, to) in arcs.iter() {
+            names.insert(from.clone());
+            names.insert(to.clone());
+        }
+
+        let names: Vec<String> = names.into_iter().collect();
+        let index: BTreeMap<&str, usize> =
+            names.iter().enumerate().map(|(ndx, name)| (name.as_str(), ndx)).collect();
+        let mut succs = vec![Vec::new(); names.len()];
+
+        for (from, to) in arcs.iter() {
+            if from != to {
+                succs[index[from.as_str()]].push(index[to.as_str()]);
+            }
+        }
+
+        let succs = break_cycles(&succs);
+        let mut preds = vec![Vec::new(); names.len()];
+
+        for (node, targets) in succs.iter().enumerate() {
+            for &target in targets {
+                preds[target].push(node);
+            }
+        }
+
+        let layer_of = assign_layers(&succs, &preds);
+        let num_layers = layer_of.iter().max().map_or(0, |&max| max + 1);
+        let mut layers = vec![Vec::new(); num_layers];
+
+        for (node, &layer) in layer_of.iter().enumerate() {
+            layers[layer].push(node);
+        }
+
+        order_layers(&mut layers, &succs, &preds);
+
+        let mut positions = BTreeMap::new();
+
+        for (layer, nodes) in layers.iter().enumerate() {
+            for (rank, &node) in nodes.iter().enumerate() {
+                positions.insert(names[node].clone(), (layer, rank));
+            }
+        }
+
+        Layout { positions, num_layers }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    #[inline]
+    pub fn get_num_layers(&self) -> usize {
+        self.num_layers
+    }
+
+    /// Returns the layer and the rank within that layer of the dot
+    /// `name`.
+    pub fn get_position<S: AsRef<str>>(&self, name: S) -> Option<(usize, usize)> {
+        self.positions.get(name.as_ref()).copied()
+    }
+
+    /// Returns an iterator over names of dots and their positions,
+    /// ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, (usize, usize))> + '_ {
+        self.positions.iter().map(|(name, &position)| (name.as_str(), position))
+    }
+
+    /// Returns this layout as a `vis` block with a single `layout`
+    /// property, the same as the one obtained by parsing its
+    /// displayed form.
+    pub fn to_prop_block(&self) -> PropBlock {
+        let mut layout = PropBlock::new();
+
+        for (name, (layer, rank)) in self.iter() {
+            let position = vec![Literal::Size(layer as u64), Literal::Size(rank as u64)];
+
+            layout = layout.with_prop(name.to_owned(), PropValue::SizeList(position));
+        }
+
+        let result = PropBlock::new().with_selector("vis".to_owned());
+
+        if self.is_empty() {
+            result
+        } else {
+            result.with_prop("layout".to_owned(), layout.into())
+        }
+    }
+}
+
+/// The layout is displayed as a `vis` block, which maps each dot to
+/// its layer and rank, e.g. `vis { layout: { a: 0 0, b: 1 0 } }`.
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.positions.is_empty() {
+            return write!(f, "vis {{ }}")
+        }
+
+        write!(f, "vis {{ layout: {{")?;
+
+        for (ndx, (name, (layer, rank))) in self.iter().enumerate() {
+            if ndx > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, " {}: {} {}", name, layer, rank)?;
+        }
+
+        write!(f, " }} }}")
+    }
+}
+
+/// Returns successor lists of an acyclic graph obtained by reversing
+/// back arcs of a depth-first search.
+fn break_cycles(succs: &[Vec<usize>]) -> Vec<BTreeSet<usize>> {
+    const NEW: u8 = 0;
+    const ON_STACK: u8 = 1;
+    const DONE: u8 = 2;
+
+    let mut state = vec![NEW; succs.len()];
+    let mut result = vec![BTreeSet::new(); succs.len()];
+
+    for start in 0..succs.len() {
+        if state[start] != NEW {
+            continue
+        }
+
+        // Iterative, since models may be large.
+        let mut stack = vec![(start, 0)];
+        state[start] = ON_STACK;
+
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+
+            if let Some(&target) = succs[node].get(*next) {
+                *next += 1;
+
+                if state[target] == ON_STACK {
+                    result[target].insert(node);
+                } else {
+                    result[node].insert(target);
+
+                    if state[target] == NEW {
+                        state[target] = ON_STACK;
+                        stack.push((target, 0));
+                    }
+                }
+            } else {
+                state[node] = DONE;
+                stack.pop();
+            }
+        }
+    }
+
+    result
+}
+
+/// Assigns each node of an acyclic graph to the layer equal to the
+/// length of the longest path reaching it.
+fn assign_layers(succs: &[BTreeSet<usize>], preds: &[Vec<usize>]) -> Vec<usize> {
+    let mut layer_of = vec![0; succs.len()];
+    let mut num_pending: Vec<usize> = preds.iter().map(|sources| sources.len()).collect();
+    let mut queue: VecDeque<usize> = (0..succs.len()).filter(|&n| num_pending[n] == 0).collect();
+
+    while let Some(node) = queue.pop_front() {
+        for &target in succs[node].iter() {
+            layer_of[target] = layer_of[target].max(layer_of[node] + 1);
+            num_pending[target] -= 1;
+
+            if num_pending[target] == 0 {
+                queue.push_back(target);
+            }
+        }
+    }
+
+    layer_of
+}
+
+/// Reorders nodes within `layers` by the barycenter heuristic.
+fn order_layers(layers: &mut [Vec<usize>], succs: &[BTreeSet<usize>], preds: &[Vec<usize>]) {
+    let mut rank_of = vec![0; succs.len()];
+
+    for nodes in layers.iter() {
+        for (rank, &node) in nodes.iter().enumerate() {
+            rank_of[node] = rank;
+        }
+    }
+
+    for _ in 0..NUM_SWEEPS {
+        for nodes in layers.iter_mut().skip(1) {
+            reorder_layer(nodes, &mut rank_of, |node| preds[node].to_vec());
+        }
+
+        for nodes in layers.iter_mut().rev().skip(1) {
+            reorder_layer(nodes, &mut rank_of, |node| succs[node].iter().copied().collect());
+        }
+    }
+}
+
+fn reorder_layer<F>(nodes: &mut [usize], rank_of: &mut [usize], neighbors_of: F)
+where
+    F: Fn(usize) -> Vec<usize>,
+{
+    // Barycenters are kept as (sum, count) pairs and compared exactly.
+    // A node without neighbors keeps its current rank.
+    let mut keyed: Vec<(usize, usize, usize)> = nodes
+        .iter()
+        .map(|&node| {
+            let neighbors = neighbors_of(node);
+
+            if neighbors.is_empty() {
+                (rank_of[node], 1, node)
+            } else {
+                (neighbors.iter().map(|&n| rank_of[n]).sum(), neighbors.len(), node)
+            }
+        })
+        .collect();
+
+    keyed.sort_by(|&(sum_a, count_a, a), &(sum_b, count_b, b)| {
+        match (sum_a * count_b).cmp(&(sum_b * count_a)) {
+            Ordering::Equal => rank_of[a].cmp(&rank_of[b]),
+            ordering => ordering,
+        }
+    });
+
+    for (rank, &(_, _, node)) in keyed.iter().enumerate() {
+        nodes[rank] = node;
+        rank_of[node] = rank;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CesFileBlock, golden};
+
+    #[test]
+    fn test_layout_layers() {
+        let layout = Layout::from_arcs(vec![("a", "b"), ("b", "c"), ("c", "a"), ("c", "d")]);
+
+        assert_eq!(layout.get_num_layers(), 4);
+        assert_eq!(layout.get_position("a"), Some((0, 0)));
+        assert_eq!(layout.get_position("b"), Some((1, 0)));
+        assert_eq!(layout.get_position("c"), Some((2, 0)));
+        assert_eq!(layout.get_position("d"), Some((3, 0)));
+    }
+
+    #[test]
+    fn test_layout_crossings() {
+        let layout = Layout::from_arcs(vec![("a", "y"), ("b", "x")]);
+
+        assert_eq!(layout.get_position("a"), Some((0, 0)));
+        assert_eq!(layout.get_position("b"), Some((0, 1)));
+        assert_eq!(layout.get_position("y"), Some((1, 0)));
+        assert_eq!(layout.get_position("x"), Some((1, 1)));
+
+        let vis = layout.to_string();
+
+        assert_eq!(vis, "vis { layout: { a: 0 0, b: 0 1, x: 1 1, y: 1 0 } }");
+        assert_eq!(vis.parse::<PropBlock>().unwrap(), layout.to_prop_block());
+        assert_eq!("vis { }".parse::<PropBlock>().unwrap(), Layout::default().to_prop_block());
+    }
+
+    #[test]
+    fn test_layout_write_back() {
+        let (ctx, mut ces_file) = golden::compile_script("ces Main { a => b c }", "Main").unwrap();
+
+        assert!(ces_file.add_layout_hints(&ctx).unwrap());
+        assert!(!ces_file.add_layout_hints(&ctx).unwrap());
+        assert_eq!(ces_file.get_layout_hints(&ctx).unwrap(), None);
+
+        if let Some(CesFileBlock::Vis(blk)) = ces_file.get_blocks().last() {
+            let vis = "vis { layout: { a: 0 0, b: 1 0, c: 1 1 } }";
+
+            assert_eq!(blk, &vis.parse::<PropBlock>().unwrap());
+        } else {
+            panic!("Missing vis block")
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std::{fmt, convert::TryFrom, str::FromStr};
 use logos::Logos;
 use enquote::unquote;
-use crate::{Weight, AscesisError, AscesisErrorKind};
+use crate::{Weight, AscesisError, AscesisErrorKind, error::format_span};
 
 #[derive(Clone, Copy, PartialEq, Logos, Debug)]
 pub enum Token<'input> {
@@ -34,6 +34,10 @@ pub enum Token<'input> {
     Dot,
     #[token(":")]
     Colon,
+    #[token("=")]
+    Equals,
+    #[token(">")]
+    Greater,
     #[token("{")]
     OpenCurly,
     #[token("}")]
@@ -62,6 +66,8 @@ pub enum Token<'input> {
     Bang,
     #[token("ces")]
     Ces,
+    #[token("poly")]
+    Poly,
     #[token("vis")]
     Vis,
     #[token("sat")]
@@ -78,6 +84,10 @@ pub enum Token<'input> {
     Activate,
     #[token("drop")]
     Drop,
+    #[token("priority")]
+    Priority,
+    #[token("let")]
+    Let,
 }
 
 impl<'input> fmt::Display for Token<'input> {
@@ -98,6 +108,8 @@ impl<'input> fmt::Display for Token<'input> {
             Comma => write!(f, ","),
             Dot => write!(f, "."),
             Colon => write!(f, ":"),
+            Equals => write!(f, "="),
+            Greater => write!(f, ">"),
             OpenCurly => write!(f, "{{"),
             CloseCurly => write!(f, "}}"),
             OpenParen => write!(f, "("),
@@ -112,6 +124,7 @@ impl<'input> fmt::Display for Token<'input> {
             FatTwowayArrow => write!(f, "<=>"),
             Bang => write!(f, "!"),
             Ces => write!(f, "ces"),
+            Poly => write!(f, "poly"),
             Vis => write!(f, "vis"),
             Sat => write!(f, "sat"),
             Caps => write!(f, "caps"),
@@ -120,6 +133,8 @@ impl<'input> fmt::Display for Token<'input> {
             Inhibit => write!(f, "inhibit"),
             Activate => write!(f, "activate"),
             Drop => write!(f, "drop"),
+            Priority => write!(f, "priority"),
+            Let => write!(f, "let"),
         }
     }
 }
@@ -161,7 +176,59 @@ impl<'input> Iterator for Lexer<'input> {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+/// Location of a parsed construct, as a range of byte offsets into
+/// the script the construct originated from.
+///
+/// Spans are carried along for diagnostic purposes only, hence the
+/// constructs holding them compare equal regardless of their spans.
+/// The default `Span` is empty and marks a construct created
+/// otherwise than by parsing.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Span {
+    start: usize,
+    end:   usize,
+}
+
+impl Span {
+    pub(crate) fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.end <= self.start
+    }
+
+    pub fn get_start(&self) -> usize {
+        self.start
+    }
+
+    pub fn get_end(&self) -> usize {
+        self.end
+    }
+
+    /// Returns the slice of `script` covered by this `Span`, if any.
+    pub fn get_text<'a>(&self, script: &'a str) -> Option<&'a str> {
+        if self.is_empty() {
+            None
+        } else {
+            script.get(self.start..self.end)
+        }
+    }
+
+    /// Returns this `Span` in the `[line:column]..[line:column]`
+    /// form, relative to `script`.
+    pub fn to_location(&self, script: &str) -> String {
+        format_span(&(self.start..self.end), script)
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum Literal {
     Size(u64),
     Omega,
@@ -192,6 +259,17 @@ impl Literal {
     }
 }
 
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Literal::Size(size) => size.fmt(f),
+            Literal::Omega => write!(f, "ω"),
+            Literal::Theta => write!(f, "θ"),
+            Literal::Name(name) => write!(f, "\"{}\"", name),
+        }
+    }
+}
+
 impl TryFrom<Literal> for u64 {
     type Error = AscesisError;
 
@@ -230,25 +308,100 @@ impl TryFrom<Literal> for Weight {
     }
 }
 
+/// Binary operators of rule expressions and arrow rules.
+///
+/// More operators may be added in the future, hence matching on
+/// `BinOp` outside of this crate requires a wildcard arm.
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[non_exhaustive]
 pub enum BinOp {
+    /// `+`, a sum of rule expressions or of monomials.
     Add,
+    /// `->`, a thin arrow from a dot list to its effect polynomial.
     ThinTx,
+    /// `<-`, a thin arrow from a dot list to its cause polynomial.
     ThinRx,
+    /// `=>`, a fat arrow from a cause polynomial to an effect
+    /// polynomial.
     FatTx,
+    /// `<=`, a fat arrow from an effect polynomial to a cause
+    /// polynomial, i.e. a reversed `=>`.
     FatRx,
+    /// `<=>`, a pair of fat arrows, `=>` and `<=`, between the same
+    /// two polynomials.
     FatDx,
 }
 
+impl BinOp {
+    /// Returns the symbol of this operator, as written in scripts.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::ThinTx => "->",
+            BinOp::ThinRx => "<-",
+            BinOp::FatTx => "=>",
+            BinOp::FatRx => "<=",
+            BinOp::FatDx => "<=>",
+        }
+    }
+
+    #[inline]
+    pub fn is_thin_arrow(&self) -> bool {
+        matches!(self, BinOp::ThinTx | BinOp::ThinRx)
+    }
+
+    #[inline]
+    pub fn is_fat_arrow(&self) -> bool {
+        matches!(self, BinOp::FatTx | BinOp::FatRx | BinOp::FatDx)
+    }
+}
+
 impl fmt::Display for BinOp {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            BinOp::Add => '+'.fmt(f),
-            BinOp::ThinTx => "->".fmt(f),
-            BinOp::ThinRx => "<-".fmt(f),
-            BinOp::FatTx => "=>".fmt(f),
-            BinOp::FatRx => "<=".fmt(f),
-            BinOp::FatDx => "<=>".fmt(f),
+        self.as_str().fmt(f)
+    }
+}
+
+impl FromStr for BinOp {
+    type Err = AscesisError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let symbol = s.trim();
+
+        match symbol {
+            "+" => Ok(BinOp::Add),
+            "->" => Ok(BinOp::ThinTx),
+            "<-" => Ok(BinOp::ThinRx),
+            "=>" => Ok(BinOp::FatTx),
+            "<=" => Ok(BinOp::FatRx),
+            "<=>" => Ok(BinOp::FatDx),
+            _ => Err(AscesisErrorKind::InvalidBinOp(symbol.to_owned()).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binop_roundtrip() {
+        use BinOp::*;
+
+        for &op in &[Add, ThinTx, ThinRx, FatTx, FatRx, FatDx] {
+            assert_eq!(op.as_str().parse::<BinOp>().unwrap(), op);
+            assert_eq!(op.to_string().parse::<BinOp>().unwrap(), op);
+        }
+
+        assert_eq!(" <=> ".parse::<BinOp>().unwrap(), FatDx);
+    }
+
+    #[test]
+    fn test_binop_invalid() {
+        for symbol in &["", "<>", "-", "=>>", "* "] {
+            let err = symbol.parse::<BinOp>().unwrap_err();
+
+            assert_eq!(err.to_string(), format!("Invalid binary operator '{}'", symbol.trim()));
         }
     }
 }
@@ -23,11 +23,19 @@ mod error;
 mod bnf;
 pub mod grammar;
 pub mod sentence;
+pub mod golden;
+pub mod differential;
+pub mod registry;
 mod axiom;
 mod ces;
 mod context;
 mod content;
 mod rex;
+mod pattern;
+mod sharing;
+mod priority;
+mod layout;
+mod provenance;
 mod polynomial;
 mod domain;
 mod lexer;
@@ -37,12 +45,18 @@ pub use aces::*;
 pub use error::{AscesisError, AscesisErrorKind};
 pub use axiom::Axiom;
 pub use ces::{CesFile, CesFileBlock, CesName, ToCesName, ImmediateDef, CesImmediate, CesInstance};
+pub(crate) use ces::ArgSlot;
 pub use context::{
     PropBlock, PropSelector, PropValue, CapacitiesBlock, UnboundedBlock, WeightsBlock,
-    InhibitorsBlock, WeightlessBlock,
+    InhibitorsBlock, WeightlessBlock, PriorityBlock,
 };
 pub use content::AscesisFormat;
 pub use rex::{Rex, ThinArrowRule, FatArrowRule};
-pub use polynomial::Polynomial;
+pub use pattern::RexPattern;
+pub use sharing::SharingStats;
+pub use priority::Priorities;
+pub use layout::Layout;
+pub use provenance::{Provenance, FitStep};
+pub use polynomial::{Polynomial, PolyDef};
 pub use domain::{DotName, ToDotName, DotList};
-pub use lexer::{Lexer, Token, Literal, BinOp};
+pub use lexer::{Lexer, Span, Token, Literal, BinOp};
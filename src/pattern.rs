@@ -0,0 +1,125 @@
+use std::{collections::BTreeSet, str::FromStr};
+use crate::{ThinArrowRule, Polynomial, DotName, AscesisError, AscesisErrorKind};
+
+const WILDCARD: &str = "_";
+
+#[derive(Clone, Default, Debug)]
+struct DotsPattern {
+    dots:    BTreeSet<DotName>,
+    is_open: bool,
+}
+
+impl DotsPattern {
+    fn new(dots: &[DotName]) -> Self {
+        let mut result = Self::default();
+
+        for dot in dots {
+            if dot.as_ref() == WILDCARD {
+                result.is_open = true;
+            } else {
+                result.dots.insert(dot.clone());
+            }
+        }
+
+        result
+    }
+
+    fn matches(&self, dots: &[DotName]) -> bool {
+        if self.is_open {
+            self.dots.iter().all(|dot| dots.contains(dot))
+        } else {
+            self.dots.len() == dots.len() && self.dots.iter().zip(dots).all(|(p, d)| p == d)
+        }
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+struct PolyPattern {
+    exact: BTreeSet<BTreeSet<DotName>>,
+    open:  Vec<BTreeSet<DotName>>,
+}
+
+impl PolyPattern {
+    fn new(poly: &Polynomial) -> Self {
+        let mut result = Self::default();
+
+        for mono in poly.monomials.iter() {
+            let mut mono = mono.clone();
+
+            if mono.remove(&DotName::from(WILDCARD.to_owned())) {
+                result.open.push(mono);
+            } else {
+                result.exact.insert(mono);
+            }
+        }
+
+        result
+    }
+
+    fn matches(&self, poly: &Polynomial) -> bool {
+        if self.exact.is_empty() && self.open.is_empty() {
+            true
+        } else if !self.exact.is_subset(&poly.monomials) {
+            false
+        } else if self.open.is_empty() {
+            self.exact == poly.monomials
+        } else {
+            self.open.iter().all(|pat| poly.monomials.iter().any(|mono| pat.is_subset(mono)))
+        }
+    }
+}
+
+/// A structural pattern to be matched against thin arrow rules.
+///
+/// Patterns are written as thin arrow rules, e.g. `_ -> b` or
+/// `a <- _`, where `_` is a wildcard standing for any (possibly
+/// empty) set of dots.  A pattern matches a rule if
+///
+/// - the dot list of the rule is equal to the dot list of the
+///   pattern, or, if the pattern's dot list contains a wildcard,
+///   includes all its other dots;
+///
+/// - for each of the pattern's cause and effect polynomials, unless
+///   omitted in the pattern, the corresponding polynomial of the rule
+///   is equal to it, or, if the pattern's polynomial contains a
+///   wildcard, the rule's polynomial contains all wildcard-free
+///   monomials of the pattern, and each monomial of the pattern
+///   containing a wildcard is included in some monomial of the rule.
+///
+/// In particular, a polynomial consisting of a single wildcard
+/// matches any nonempty polynomial.
+#[derive(Clone, Debug)]
+pub struct RexPattern {
+    dots:   DotsPattern,
+    cause:  PolyPattern,
+    effect: PolyPattern,
+}
+
+impl RexPattern {
+    pub fn matches(&self, tar: &ThinArrowRule) -> bool {
+        self.dots.matches(tar.get_dots())
+            && self.cause.matches(tar.get_cause())
+            && self.effect.matches(tar.get_effect())
+    }
+}
+
+impl From<&ThinArrowRule> for RexPattern {
+    fn from(tar: &ThinArrowRule) -> Self {
+        RexPattern {
+            dots:   DotsPattern::new(tar.get_dots()),
+            cause:  PolyPattern::new(tar.get_cause()),
+            effect: PolyPattern::new(tar.get_effect()),
+        }
+    }
+}
+
+impl FromStr for RexPattern {
+    type Err = AscesisError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tar: ThinArrowRule =
+            s.parse().map_err(|err| AscesisErrorKind::from(err).with_script(s))?;
+
+        Ok(RexPattern::from(&tar))
+    }
+}
@@ -1,6 +1,10 @@
-use std::{collections::BTreeSet, iter::FromIterator};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    iter::FromIterator,
+    fmt,
+};
 use aces::{ContextHandle, DotId};
-use crate::{DotName, ToDotName, DotList};
+use crate::{DotName, ToDotName, DotList, AscesisError, AscesisErrorKind};
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub(crate) enum Warning {
@@ -87,10 +91,41 @@ impl Polynomial {
                     self.monomials.insert(mono);
                 }
             }
+
+            self.warnings.append(&mut factor.warnings);
         }
         self.log_warnings();
     }
 
+    /// Replaces each occurrence of `dot` with `replacement`.
+    ///
+    /// Every monomial containing `dot` is replaced with the sum of
+    /// products of that monomial (with `dot` removed) and monomials
+    /// of `replacement`.
+    pub(crate) fn substitute(&mut self, dot: &DotName, replacement: &Self) {
+        if !self.monomials.iter().any(|mono| mono.contains(dot)) {
+            return
+        }
+
+        if !replacement.is_flat {
+            self.is_flat = false;
+        }
+
+        let old_monos = std::mem::take(&mut self.monomials);
+
+        for mut mono in old_monos.into_iter() {
+            if mono.remove(dot) {
+                for other_mono in replacement.monomials.iter() {
+                    let mut new_mono = mono.clone();
+                    new_mono.extend(other_mono.iter().cloned());
+                    self.monomials.insert(new_mono);
+                }
+            } else {
+                self.monomials.insert(mono);
+            }
+        }
+    }
+
     pub(crate) fn add_assign(&mut self, other: &mut Self) {
         self.is_flat = false;
 
@@ -101,9 +136,16 @@ impl Polynomial {
         }
 
         self.monomials.append(&mut other.monomials);
+        self.warnings.append(&mut other.warnings);
         self.log_warnings();
     }
 
+    /// Returns an iterator over all [`DotName`]s occurring in this
+    /// `Polynomial`, possibly with repetitions.
+    pub(crate) fn get_dot_names(&self) -> impl Iterator<Item = DotName> + '_ {
+        self.monomials.iter().flat_map(|mono| mono.iter().cloned())
+    }
+
     pub(crate) fn compile_as_vec(&self, ctx: &ContextHandle) -> Vec<Vec<DotId>> {
         let mut ctx = ctx.lock().unwrap();
 
@@ -133,6 +175,30 @@ impl Default for Polynomial {
     }
 }
 
+impl fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.monomials.is_empty() {
+            return "θ".fmt(f)
+        }
+
+        for (ndx, mono) in self.monomials.iter().enumerate() {
+            if ndx > 0 {
+                " + ".fmt(f)?;
+            }
+
+            if mono.is_empty() {
+                "θ".fmt(f)?;
+            } else {
+                let dots: Vec<&str> = mono.iter().map(|dot| dot.as_ref()).collect();
+
+                dots.join(" ").fmt(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl From<DotName> for Polynomial {
     fn from(dot: DotName) -> Self {
         Polynomial {
@@ -226,6 +292,189 @@ impl From<Vec<DotList>> for Polynomial {
     }
 }
 
+/// A named polynomial, declared as `poly P = a(b + c);`.
+///
+/// The name of a `PolyDef` may be used wherever a polynomial is
+/// expected in a rule expression, and it is expanded during
+/// resolution of a [`CesFile`].
+///
+/// [`CesFile`]: crate::CesFile
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PolyDef {
+    name: String,
+    poly: Polynomial,
+}
+
+impl PolyDef {
+    pub fn new(name: String, poly: Polynomial) -> Self {
+        debug!("PolyDef of '{}': {:?}", name, poly);
+        PolyDef { name, poly }
+    }
+
+    #[inline]
+    pub fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    #[inline]
+    pub fn get_polynomial(&self) -> &Polynomial {
+        &self.poly
+    }
+}
+
+/// A map from names of polynomial abbreviations to their expansions.
+///
+/// Expanded polynomials contain no references to other
+/// abbreviations.
+#[derive(Clone, Default, Debug)]
+pub(crate) struct PolyAbbreviations {
+    expanded: BTreeMap<DotName, Polynomial>,
+}
+
+impl PolyAbbreviations {
+    /// Expands all `defs` in terms of each other.
+    ///
+    /// Returns an error if an abbreviation is defined more than once,
+    /// if expansion of an abbreviation requires its own expansion, or
+    /// if an abbreviation is repeated in a product, as described in
+    /// [`PolyAbbreviations::apply`].
+    pub(crate) fn resolve<'a, I>(defs: I) -> Result<Self, AscesisError>
+    where
+        I: IntoIterator<Item = &'a PolyDef>,
+    {
+        let mut unexpanded = BTreeMap::new();
+
+        for def in defs {
+            if unexpanded.insert(def.name.to_dot(), &def.poly).is_some() {
+                return Err(AscesisErrorKind::PolyRedefined(def.name.clone()).into())
+            }
+        }
+
+        let mut result = Self::default();
+        let mut stack = Vec::new();
+
+        for name in unexpanded.keys() {
+            result.expand(name, &unexpanded, &mut stack)?;
+        }
+
+        Ok(result)
+    }
+
+    fn expand(
+        &mut self,
+        name: &DotName,
+        unexpanded: &BTreeMap<DotName, &Polynomial>,
+        stack: &mut Vec<DotName>,
+    ) -> Result<(), AscesisError> {
+        if self.expanded.contains_key(name) {
+            return Ok(())
+        }
+
+        if let Some(pos) = stack.iter().position(|n| n == name) {
+            let mut cycle: Vec<String> =
+                stack[pos..].iter().map(|n| n.as_ref().to_owned()).collect();
+            cycle.push(name.as_ref().to_owned());
+
+            return Err(AscesisErrorKind::PolyCycle(cycle).into())
+        }
+
+        let mut poly = unexpanded[name].clone();
+        let deps: BTreeSet<DotName> =
+            poly.get_dot_names().filter(|dot| unexpanded.contains_key(dot)).collect();
+
+        stack.push(name.clone());
+
+        for dep in deps.iter() {
+            self.expand(dep, unexpanded, stack)?;
+        }
+
+        self.check_repetitions(&poly)?;
+
+        for dep in deps.iter() {
+            poly.substitute(dep, &self.expanded[dep]);
+        }
+
+        stack.pop();
+        self.expanded.insert(name.clone(), poly);
+
+        Ok(())
+    }
+
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.expanded.is_empty()
+    }
+
+    /// Replaces all references to abbreviations in `poly` with their
+    /// expansions.
+    ///
+    /// Returns an error if an abbreviation expanding to more than one
+    /// monomial is repeated in a product.  Such a product has already
+    /// been reduced by idempotency, before expansion, so that its
+    /// expansion would differ from the textual one.
+    pub(crate) fn apply(&self, poly: &mut Polynomial) -> Result<(), AscesisError> {
+        let refs: BTreeSet<DotName> =
+            poly.get_dot_names().filter(|dot| self.expanded.contains_key(dot)).collect();
+
+        if refs.is_empty() {
+            return Ok(())
+        }
+
+        self.check_repetitions(poly)?;
+
+        for name in refs.iter() {
+            poly.substitute(name, &self.expanded[name]);
+        }
+
+        Ok(())
+    }
+
+    fn check_repetitions(&self, poly: &Polynomial) -> Result<(), AscesisError> {
+        for warning in poly.warnings.iter() {
+            if let Warning::ProductIdempotency(dot) = warning {
+                if let Some(expansion) = self.expanded.get(dot) {
+                    if expansion.monomials.len() > 1 {
+                        return Err(
+                            AscesisErrorKind::PolyRepeated(dot.as_ref().to_owned()).into()
+                        )
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Replaces all references to abbreviations in `dots` with their
+    /// expansions.
+    ///
+    /// Returns an error if the expansion of a referenced abbreviation
+    /// isn't a single monomial, hence isn't a list of dots.
+    pub(crate) fn apply_to_dots(&self, dots: &mut DotList) -> Result<(), AscesisError> {
+        if !dots.dot_names.iter().any(|dot| self.expanded.contains_key(dot)) {
+            return Ok(())
+        }
+
+        let mut dot_names = Vec::new();
+
+        for dot in dots.dot_names.iter() {
+            if let Some(poly) = self.expanded.get(dot) {
+                if poly.monomials.len() == 1 {
+                    dot_names.extend(poly.get_dot_names());
+                } else {
+                    return Err(AscesisErrorKind::PolyNotADotList(dot.as_ref().to_owned()).into())
+                }
+            } else {
+                dot_names.push(dot.clone());
+            }
+        }
+
+        *dots = DotList::from(dot_names);
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ToDotName;
@@ -253,4 +502,88 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_poly_abbreviations() {
+        let defs = [
+            PolyDef::new("P".to_owned(), "a Q".parse().unwrap()),
+            PolyDef::new("Q".to_owned(), "b + c".parse().unwrap()),
+        ];
+        let abbrevs = PolyAbbreviations::resolve(defs.iter()).unwrap();
+        let mut poly: Polynomial = "P d + e".parse().unwrap();
+
+        abbrevs.apply(&mut poly).unwrap();
+
+        assert_eq!(
+            poly,
+            Polynomial::from(vec![vec!["a", "b", "d"], vec!["a", "c", "d"], vec!["e"]])
+        );
+    }
+
+    #[test]
+    fn test_poly_abbreviation_textual() {
+        let defs = [
+            PolyDef::new("P".to_owned(), "a (b + c)".parse().unwrap()),
+            PolyDef::new("Q".to_owned(), "a + b".parse().unwrap()),
+            PolyDef::new("R".to_owned(), "a + b".parse().unwrap()),
+            PolyDef::new("S".to_owned(), "a b".parse().unwrap()),
+        ];
+        let abbrevs = PolyAbbreviations::resolve(defs.iter()).unwrap();
+
+        for (phrase, textual) in &[
+            ("P d + e", "a (b + c) d + e"),
+            ("Q R", "(a + b) (a + b)"),
+            ("Q (R + d)", "(a + b) ((a + b) + d)"),
+            ("S S c", "a b a b c"),
+        ] {
+            let mut poly: Polynomial = phrase.parse().unwrap();
+            let textual: Polynomial = textual.parse().unwrap();
+
+            abbrevs.apply(&mut poly).unwrap();
+            assert_eq!(poly.monomials, textual.monomials);
+        }
+
+        for phrase in &["P P", "(P + d) (P + e)", "Q d + Q Q"] {
+            let mut poly: Polynomial = phrase.parse().unwrap();
+            let err = abbrevs.apply(&mut poly).unwrap_err();
+
+            assert!(err.to_string().starts_with("Polynomial abbreviation"));
+        }
+
+        let defs = [
+            PolyDef::new("Q".to_owned(), "a + b".parse().unwrap()),
+            PolyDef::new("T".to_owned(), "Q c Q".parse().unwrap()),
+        ];
+
+        assert!(PolyAbbreviations::resolve(defs.iter()).is_err());
+    }
+
+    #[test]
+    fn test_poly_abbreviation_cycle() {
+        let defs = [
+            PolyDef::new("P".to_owned(), "a Q".parse().unwrap()),
+            PolyDef::new("Q".to_owned(), "b + R".parse().unwrap()),
+            PolyDef::new("R".to_owned(), "P".parse().unwrap()),
+        ];
+
+        assert!(PolyAbbreviations::resolve(defs.iter()).is_err());
+    }
+
+    #[test]
+    fn test_poly_abbreviations_in_dots() {
+        let defs = [
+            PolyDef::new("P".to_owned(), "a b".parse().unwrap()),
+            PolyDef::new("Q".to_owned(), "b + c".parse().unwrap()),
+        ];
+        let abbrevs = PolyAbbreviations::resolve(defs.iter()).unwrap();
+        let mut dots = DotList::from(vec!["P", "c"]);
+
+        abbrevs.apply_to_dots(&mut dots).unwrap();
+        assert_eq!(dots, DotList::from(vec!["a", "b", "c"]));
+
+        let mut dots = DotList::from(vec!["Q"]);
+        let err = abbrevs.apply_to_dots(&mut dots).unwrap_err();
+
+        assert_eq!(err.to_string(), "Polynomial abbreviation 'Q' used as a dot list");
+    }
 }
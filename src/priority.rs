@@ -0,0 +1,248 @@
+use std::collections::{BTreeMap, BTreeSet};
+use aces::{ContextHandle, Content, DotId};
+use crate::{CesName, ToCesName, PriorityBlock, AscesisError, AscesisErrorKind};
+
+/// A strict partial order of structures, resolved from all
+/// [`PriorityBlock`]s of a [`CesFile`].
+///
+/// A structure is declared to take precedence over all structures
+/// below it.  Structures unrelated by the order, including structures
+/// not mentioned in any priority block, have equal precedence.
+///
+/// Content merged from several structures doesn't record which
+/// structure contributed which rule.  Instead, once a [`CesFile`] is
+/// compiled, each structure mentioned in the order is represented by
+/// its footprint, i.e. by the dots of its compiled content.  Anything
+/// given by its dots, e.g. a firing enabled during simulation,
+/// belongs to every structure whose footprint contains all of these
+/// dots, see [`Priorities::select`].
+///
+/// [`CesFile`]: crate::CesFile
+#[derive(Clone, Default, Debug)]
+pub struct Priorities {
+    // Transitively closed, i.e. maps each name to all names below it.
+    below:      BTreeMap<CesName, BTreeSet<CesName>>,
+    footprints: BTreeMap<CesName, BTreeSet<DotId>>,
+}
+
+impl Priorities {
+    /// Merges all `blocks` into a single relation and computes its
+    /// transitive closure.
+    ///
+    /// Returns an error if the merged relation has a cycle, including
+    /// a structure declared to take precedence over itself.
+    pub(crate) fn resolve<'a, I>(blocks: I) -> Result<Self, AscesisError>
+    where
+        I: IntoIterator<Item = &'a PriorityBlock>,
+    {
+        let mut declared: BTreeMap<CesName, BTreeSet<CesName>> = BTreeMap::new();
+
+        for block in blocks {
+            for (higher, lower) in block.iter_pairs() {
+                declared.entry(higher.clone()).or_default().insert(lower.clone());
+            }
+        }
+
+        let mut result = Self::default();
+        let mut stack = Vec::new();
+
+        for name in declared.keys() {
+            result.close(name, &declared, &mut stack)?;
+        }
+
+        Ok(result)
+    }
+
+    fn close(
+        &mut self,
+        name: &CesName,
+        declared: &BTreeMap<CesName, BTreeSet<CesName>>,
+        stack: &mut Vec<CesName>,
+    ) -> Result<(), AscesisError> {
+        if self.below.contains_key(name) {
+            return Ok(())
+        }
+
+        if let Some(pos) = stack.iter().position(|n| n == name) {
+            let mut cycle: Vec<String> = stack[pos..].iter().map(|n| (**n).clone()).collect();
+            cycle.push((**name).clone());
+
+            return Err(AscesisErrorKind::PriorityCycle(cycle).into())
+        }
+
+        let mut below = BTreeSet::new();
+
+        if let Some(lower) = declared.get(name) {
+            stack.push(name.clone());
+
+            for low in lower.iter() {
+                self.close(low, declared, stack)?;
+                below.insert(low.clone());
+                below.extend(self.below[low].iter().cloned());
+            }
+
+            stack.pop();
+        }
+
+        self.below.insert(name.clone(), below);
+
+        Ok(())
+    }
+
+    /// Records footprints of all structures mentioned in the order,
+    /// which are compiled into the context.
+    pub(crate) fn gather_footprints(&mut self, ctx: &ContextHandle) {
+        let ctx = ctx.lock().unwrap();
+
+        self.footprints.clear();
+
+        for name in self.below.keys() {
+            if let Some(content) = ctx.get_content(name) {
+                let footprint = content.clone().get_carrier_ids().into_iter().collect();
+
+                self.footprints.insert(name.clone(), footprint);
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.below.values().all(|lower| lower.is_empty())
+    }
+
+    /// Returns an iterator over names of all structures mentioned in
+    /// priority blocks.
+    pub fn get_names(&self) -> impl Iterator<Item = &CesName> + '_ {
+        self.below.keys()
+    }
+
+    /// Returns `true` if structure `higher` takes precedence over
+    /// structure `lower`, directly or transitively.
+    pub fn is_preferred<S: AsRef<str>, T: AsRef<str>>(&self, higher: S, lower: T) -> bool {
+        if let Some(below) = self.below.get(&higher.to_ces_name()) {
+            below.contains(&lower.to_ces_name())
+        } else {
+            false
+        }
+    }
+
+    /// Returns the footprint of structure `name`, or `None` if the
+    /// structure isn't mentioned in the order or wasn't compiled.
+    pub fn get_footprint<S: AsRef<str>>(&self, name: S) -> Option<&BTreeSet<DotId>> {
+        self.footprints.get(&name.to_ces_name())
+    }
+
+    /// Returns names of all structures mentioned in the order, whose
+    /// footprints contain all `dots`.
+    pub fn get_owners(&self, dots: &[DotId]) -> Vec<&CesName> {
+        self.footprints
+            .iter()
+            .filter(|(_, footprint)| dots.iter().all(|dot| footprint.contains(dot)))
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Selects those of the `enabled` items, each given by its dots,
+    /// which aren't overridden by another enabled item.
+    ///
+    /// An item is overridden if some structure owning another item
+    /// takes precedence over all structures owning this item.  Items
+    /// owned by no structure mentioned in the order are never
+    /// overridden.
+    ///
+    /// The relative order of selected items is preserved.  Since the
+    /// order is acyclic, the result is nonempty, unless `enabled` is.
+    pub fn select<I, T>(&self, enabled: I) -> Vec<T>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[DotId]>,
+    {
+        let enabled: Vec<T> = enabled.into_iter().collect();
+        let owners: Vec<Vec<&CesName>> =
+            enabled.iter().map(|item| self.get_owners(item.as_ref())).collect();
+
+        let is_overridden = |ndx: usize| {
+            !owners[ndx].is_empty()
+                && owners.iter().enumerate().any(|(other_ndx, other_owners)| {
+                    other_ndx != ndx
+                        && other_owners.iter().any(|higher| {
+                            owners[ndx].iter().all(|lower| self.is_preferred(higher, lower))
+                        })
+                })
+        };
+        let selected: Vec<bool> = (0..enabled.len()).map(|ndx| !is_overridden(ndx)).collect();
+
+        enabled
+            .into_iter()
+            .zip(selected)
+            .filter(|(_, is_selected)| *is_selected)
+            .map(|(item, _)| item)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::golden;
+    use super::*;
+
+    #[test]
+    fn test_priorities() {
+        let phrase = "priority { spawn > cleanup, main > spawn idle, idle > cleanup, }";
+        let block: PriorityBlock = phrase.parse().unwrap();
+        let priorities = Priorities::resolve(Some(&block)).unwrap();
+
+        assert!(priorities.is_preferred("main", "cleanup"));
+        assert!(priorities.is_preferred("spawn", "cleanup"));
+        assert!(!priorities.is_preferred("spawn", "idle"));
+        assert!(!priorities.is_preferred("cleanup", "spawn"));
+    }
+
+    fn dot_ids(ctx: &ContextHandle, names: &str) -> Vec<DotId> {
+        let ctx = ctx.lock().unwrap();
+
+        names.split_whitespace().map(|name| ctx.get_dot_id(name).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_priorities_selection() {
+        let script = "ces Spawn { a => b }\nces Cleanup { a => c }\nces Idle { d => e }\n\
+                      ces Main { Spawn() + Cleanup() + Idle() }";
+        let prioritized = format!("{}\npriority {{ Spawn > Cleanup }}", script);
+
+        let (ctx, ces_file) = golden::compile_script(&prioritized, "Main").unwrap();
+        let spawn = dot_ids(&ctx, "a b");
+        let cleanup = dot_ids(&ctx, "a c");
+        let idle = dot_ids(&ctx, "d e");
+        let shared = dot_ids(&ctx, "a");
+
+        assert_eq!(
+            ces_file.get_priorities().unwrap().get_footprint("Spawn"),
+            Some(&spawn.iter().copied().collect())
+        );
+        assert_eq!(ces_file.select_enabled(vec![&cleanup, &idle, &spawn]), [&idle, &spawn]);
+        assert_eq!(ces_file.select_enabled(vec![&cleanup, &idle]), [&cleanup, &idle]);
+        assert_eq!(ces_file.select_enabled(vec![&cleanup, &shared]), [&shared]);
+        assert_eq!(ces_file.select_enabled(vec![&spawn, &shared]), [&spawn, &shared]);
+
+        let (_, ces_file) = golden::compile_script(script, "Main").unwrap();
+
+        assert_eq!(
+            ces_file.select_enabled(vec![&cleanup, &idle, &spawn]),
+            [&cleanup, &idle, &spawn]
+        );
+
+        let unknown = format!("{}\npriority {{ X > Main }}", script);
+
+        assert!(golden::compile_script(unknown, "Main").is_err());
+    }
+
+    #[test]
+    fn test_priority_cycle() {
+        let phrase = "priority { a > b > c, c > a }";
+        let block: PriorityBlock = phrase.parse().unwrap();
+        let err = Priorities::resolve(Some(&block)).unwrap_err();
+
+        assert_eq!(err.to_string(), "Cyclic priority 'a' > 'b' > 'c' > 'a'");
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+use aces::Polarity;
+use crate::{CesName, ThinArrowRule, Span};
+
+/// A step of FIT (fat-into-thin) transformation, through which a fat
+/// arrow rule contributes to compiled content.
+///
+/// Later steps of FIT only integrate and merge thin arrow rules,
+/// which doesn't change their contributions, hence these steps
+/// aren't recorded.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FitStep {
+    /// A fat arrow rule of more than two polynomials, or a two-way
+    /// rule, was split into two-polynomial parts, and the part of
+    /// this index (counting from zero) contributed.
+    Split(usize),
+    /// A two-polynomial fat arrow rule was replaced with an
+    /// effect-only and a cause-only thin arrow rule.
+    Thinning,
+}
+
+impl fmt::Display for FitStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FitStep::Split(ndx) => write!(f, "split into part #{}", ndx),
+            FitStep::Thinning => write!(f, "thinning"),
+        }
+    }
+}
+
+/// A source construct, which contributed an entry between two dots
+/// to compiled content, as reported by [`CesFile::explain`].
+///
+/// [`CesFile::explain`]: crate::CesFile::explain
+#[derive(Clone, Debug)]
+pub struct Provenance {
+    chain:     Vec<(CesName, Span)>,
+    rule:      ThinArrowRule,
+    polarity:  Polarity,
+    fit_steps: Vec<FitStep>,
+}
+
+impl Provenance {
+    pub(crate) fn new(
+        chain: Vec<(CesName, Span)>,
+        rule: ThinArrowRule,
+        polarity: Polarity,
+        fit_steps: Vec<FitStep>,
+    ) -> Self {
+        Provenance { chain, rule, polarity, fit_steps }
+    }
+
+    /// Returns the chain of instantiations leading from the root
+    /// structure to the definition containing the contributing
+    /// construct.  Each structure is paired with the location of its
+    /// instantiation, which is empty for the root.
+    #[inline]
+    pub fn get_chain(&self) -> &[(CesName, Span)] {
+        &self.chain
+    }
+
+    /// Returns the name of the structure, whose definition contains
+    /// the contributing construct.
+    #[inline]
+    pub fn get_definition(&self) -> &CesName {
+        &self.chain.last().unwrap().0
+    }
+
+    /// Returns the contributing thin arrow rule, which is either
+    /// written in the script, or, if [`get_fit_steps`] isn't empty,
+    /// obtained from a fat arrow rule.
+    ///
+    /// [`get_fit_steps`]: Provenance::get_fit_steps
+    #[inline]
+    pub fn get_rule(&self) -> &ThinArrowRule {
+        &self.rule
+    }
+
+    /// Returns the location of the contributing construct, i.e. of
+    /// the thin or fat arrow rule.
+    #[inline]
+    pub fn get_span(&self) -> Span {
+        self.rule.get_span()
+    }
+
+    /// Returns `Tx`, if the entry is contributed to the effect
+    /// polynomial of the cause dot, or `Rx`, if it is contributed to
+    /// the cause polynomial of the effect dot.
+    #[inline]
+    pub fn get_polarity(&self) -> Polarity {
+        self.polarity
+    }
+
+    #[inline]
+    pub fn get_fit_steps(&self) -> &[FitStep] {
+        &self.fit_steps
+    }
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<&str> = self.chain.iter().map(|(name, _)| name.as_str()).collect();
+        let polarity = match self.polarity {
+            Polarity::Tx => "effect",
+            Polarity::Rx => "cause",
+        };
+
+        write!(f, "{} ({}) at {} in {}", self.rule, polarity, self.get_span(), names.join(" > "))?;
+
+        for (ndx, step) in self.fit_steps.iter().enumerate() {
+            write!(f, "{}{}", if ndx > 0 { ", " } else { ", FIT: " }, step)?;
+        }
+
+        Ok(())
+    }
+}
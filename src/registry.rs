@@ -0,0 +1,314 @@
+//! Persistent registry of named models and their version history.
+//!
+//! A registry is a directory holding a subdirectory for each model,
+//! named after the model's root structure.  Each published version
+//! `N` of a model is stored as the script `N.ces`, along with the
+//! model's text, `N.snap`.  The `index` file of a model lists all
+//! versions, one per line, in the form `N fingerprint +added
+//! -removed`.
+//!
+//! The text of a model is its compiled content in the snapshot form
+//! of the [`golden`] module, followed by all blocks of the script,
+//! which don't show in compiled content, i.e. capacities, weights,
+//! inhibitors, and other property blocks.  These are written one per
+//! line, in their displayed form and in the order of the script.
+//!
+//! The `fingerprint` is a hash of the text of a model.  Versions are
+//! identified by their fingerprints, not by their scripts, hence
+//! publishing a script, which differs from the latest version only in
+//! layout of rule expressions, doesn't create a new version.
+//!
+//! The last two fields of an index line count lines of the model's
+//! text added and removed since the previous version.  These
+//! counters, as well as [`Registry::diff`], are based on a plain line
+//! diff, not on any semantic comparison of models.
+//!
+//! [`golden`]: crate::golden
+
+use std::{
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    error::Error,
+};
+use aces::{ContextHandle, Content};
+use crate::{CesFile, CesFileBlock, AscesisError, AscesisErrorKind, golden};
+
+const INDEX_NAME: &str = "index";
+
+/// Returns the 64-bit FNV-1a hash of `text`.
+pub fn fingerprint(text: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    text.bytes().fold(OFFSET_BASIS, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(PRIME))
+}
+
+/// Returns the text of a model compiled from `ces_file`, see the
+/// [module documentation] for its form.
+///
+/// [module documentation]: crate::registry
+fn serialize_model(ctx: &ContextHandle, ces_file: &mut CesFile) -> String {
+    let mut result = golden::serialize(ctx, ces_file);
+
+    for block in ces_file.get_blocks() {
+        let line = match block {
+            CesFileBlock::Vis(block) | CesFileBlock::SAT(block) => block.to_string(),
+            CesFileBlock::Caps(block) => block.to_string(),
+            CesFileBlock::Unbounded(block) => block.to_string(),
+            CesFileBlock::Weights(block) => block.to_string(),
+            CesFileBlock::Inhibit(block) => block.to_string(),
+            CesFileBlock::Activate(block) | CesFileBlock::Drop(block) => block.to_string(),
+            CesFileBlock::Priority(block) => block.to_string(),
+            CesFileBlock::Imm(_) | CesFileBlock::Poly(_) | CesFileBlock::Bad(_) => continue,
+        };
+
+        result.push_str(&line);
+        result.push('\n');
+    }
+
+    result
+}
+
+/// A single entry of a model's version history.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ModelVersion {
+    version:     u32,
+    fingerprint: u64,
+    added:       usize,
+    removed:     usize,
+}
+
+impl ModelVersion {
+    fn from_index_line(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace();
+        let version = fields.next()?.parse().ok()?;
+        let fingerprint = u64::from_str_radix(fields.next()?, 16).ok()?;
+        let added = fields.next()?.strip_prefix('+')?.parse().ok()?;
+        let removed = fields.next()?.strip_prefix('-')?.parse().ok()?;
+
+        if fields.next().is_none() {
+            Some(ModelVersion { version, fingerprint, added, removed })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn get_version(&self) -> u32 {
+        self.version
+    }
+
+    #[inline]
+    pub fn get_fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// Returns the number of lines of the model's text added since
+    /// the previous version.
+    #[inline]
+    pub fn get_added(&self) -> usize {
+        self.added
+    }
+
+    /// Returns the number of lines of the model's text removed since
+    /// the previous version.
+    #[inline]
+    pub fn get_removed(&self) -> usize {
+        self.removed
+    }
+}
+
+impl fmt::Display for ModelVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {:016x} +{} -{}", self.version, self.fingerprint, self.added, self.removed)
+    }
+}
+
+/// An on-disk store of models, see the [module documentation] for
+/// its layout.
+///
+/// [module documentation]: crate::registry
+#[derive(Clone, Debug)]
+pub struct Registry {
+    root: PathBuf,
+}
+
+impl Registry {
+    /// Opens the registry stored in directory `root`, creating the
+    /// directory, if necessary.
+    pub fn open<P: AsRef<Path>>(root: P) -> Result<Self, Box<dyn Error>> {
+        let root = root.as_ref().to_path_buf();
+
+        fs::create_dir_all(&root)?;
+
+        Ok(Registry { root })
+    }
+
+    #[inline]
+    pub fn get_root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Returns names of all published models, in alphabetical order.
+    pub fn get_names(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut result = Vec::new();
+
+        for entry in fs::read_dir(&self.root)? {
+            let path = entry?.path();
+
+            if path.join(INDEX_NAME).is_file() {
+                if let Some(name) = path.file_name().and_then(|name| name.to_str()) {
+                    result.push(name.to_owned());
+                }
+            }
+        }
+
+        result.sort();
+
+        Ok(result)
+    }
+
+    fn read_index(&self, name: &str) -> Result<Vec<ModelVersion>, Box<dyn Error>> {
+        let path = self.root.join(name).join(INDEX_NAME);
+        let index = match fs::read_to_string(&path) {
+            Ok(index) => index,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut result = Vec::new();
+
+        for line in index.lines().filter(|line| !line.trim().is_empty()) {
+            if let Some(entry) = ModelVersion::from_index_line(line) {
+                result.push(entry);
+            } else {
+                return Err(AscesisError::from(AscesisErrorKind::RegistryCorrupted(
+                    path.display().to_string(),
+                ))
+                .into())
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn get_version_path(&self, name: &str, version: u32, extension: &str) -> PathBuf {
+        self.root.join(name).join(format!("{}.{}", version, extension))
+    }
+
+    /// Compiles `ces_file` in a fresh context, starting from its root
+    /// structure, and stores the result as the next version of the
+    /// model named after the root.
+    ///
+    /// Returns the new history entry or, if the fingerprint didn't
+    /// change since the latest version, that version's entry.
+    pub fn publish(&self, ces_file: &CesFile) -> Result<ModelVersion, Box<dyn Error>> {
+        let name = ces_file.get_root()?.get_name().as_str();
+        let script = match ces_file.get_script() {
+            Some(script) => script,
+            None => return Err(AscesisError::from(AscesisErrorKind::ScriptMissing).into()),
+        };
+        let (ctx, mut compiled) = golden::compile_script(script, name)?;
+        let text = serialize_model(&ctx, &mut compiled);
+        let fingerprint = fingerprint(&text);
+        let history = self.read_index(name)?;
+
+        let (version, added, removed) = if let Some(latest) = history.last() {
+            if latest.fingerprint == fingerprint {
+                info!("Model '{}' unchanged since version {}", name, latest.version);
+
+                return Ok(latest.clone())
+            }
+
+            let previous = fs::read_to_string(self.get_version_path(name, latest.version, "snap"))?;
+            let diff = golden::diff(&previous, &text).unwrap_or_default();
+            let added = diff.lines().filter(|line| line.starts_with('+')).count();
+            let removed = diff.lines().filter(|line| line.starts_with('-')).count();
+
+            (latest.version + 1, added, removed)
+        } else {
+            (1, text.lines().count(), 0)
+        };
+
+        fs::create_dir_all(self.root.join(name))?;
+        fs::write(self.get_version_path(name, version, "ces"), script)?;
+        fs::write(self.get_version_path(name, version, "snap"), &text)?;
+
+        let entry = ModelVersion { version, fingerprint, added, removed };
+        let mut index = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.root.join(name).join(INDEX_NAME))?;
+
+        writeln!(index, "{}", entry)?;
+        info!("Published version {} of model '{}'", version, name);
+
+        Ok(entry)
+    }
+
+    /// Returns all versions of the model `name`, oldest first.
+    pub fn history<S: AsRef<str>>(&self, name: S) -> Result<Vec<ModelVersion>, Box<dyn Error>> {
+        let name = name.as_ref();
+        let history = self.read_index(name)?;
+
+        if history.is_empty() {
+            Err(AscesisError::from(AscesisErrorKind::ModelUnknown(name.to_owned())).into())
+        } else {
+            Ok(history)
+        }
+    }
+
+    fn read_version<S: AsRef<str>>(
+        &self,
+        name: S,
+        version: u32,
+        extension: &str,
+    ) -> Result<String, Box<dyn Error>> {
+        let name = name.as_ref();
+
+        if self.history(name)?.iter().any(|entry| entry.version == version) {
+            Ok(fs::read_to_string(self.get_version_path(name, version, extension))?)
+        } else {
+            Err(AscesisError::from(AscesisErrorKind::ModelVersionUnknown(name.to_owned(), version))
+                .into())
+        }
+    }
+
+    /// Returns the script of the given `version` of the model `name`.
+    pub fn get_script<S: AsRef<str>>(
+        &self,
+        name: S,
+        version: u32,
+    ) -> Result<String, Box<dyn Error>> {
+        self.read_version(name, version, "ces")
+    }
+
+    /// Returns the text of the given `version` of the model `name`,
+    /// i.e. its compiled content in the snapshot form, followed by
+    /// its property blocks.
+    pub fn get_snapshot<S: AsRef<str>>(
+        &self,
+        name: S,
+        version: u32,
+    ) -> Result<String, Box<dyn Error>> {
+        self.read_version(name, version, "snap")
+    }
+
+    /// Returns a line diff of texts of two versions of the model
+    /// `name`, or `None` if both are equal.
+    ///
+    /// See [`golden::diff`] for the format.
+    pub fn diff<S: AsRef<str>>(
+        &self,
+        name: S,
+        old_version: u32,
+        new_version: u32,
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        let name = name.as_ref();
+        let old = self.get_snapshot(name, old_version)?;
+        let new = self.get_snapshot(name, new_version)?;
+
+        Ok(golden::diff(&old, &new))
+    }
+}
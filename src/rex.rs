@@ -1,9 +1,15 @@
-use std::{convert::TryInto, error::Error};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryInto,
+    fmt,
+    error::Error,
+};
 use log::Level::Debug;
-use aces::{ContextHandle, PartialContent, CompilableAsContent};
+use aces::{ContextHandle, PartialContent, CompilableAsContent, Polarity};
 use crate::{
-    CesImmediate, CesInstance, DotName, DotList, BinOp, polynomial::Polynomial, AscesisError,
-    AscesisErrorKind,
+    CesImmediate, CesInstance, CesName, ToCesName, DotName, DotList, BinOp, Span, RexPattern,
+    polynomial::{Polynomial, PolyAbbreviations},
+    Provenance, FitStep, AscesisError, AscesisErrorKind,
 };
 
 pub(crate) type RexID = usize;
@@ -116,6 +122,217 @@ impl Rex {
         }
     }
 
+    /// Returns this `Rex` with all references to local `bindings`
+    /// replaced by bound expressions.
+    ///
+    /// A reference to a binding has the form of an immediate
+    /// instantiation, `name()`.  Bindings are resolved in order, so
+    /// that a bound expression may refer to preceding bindings.  A
+    /// binding shadows any earlier binding of the same name.  Since
+    /// the scope of structure names isn't known here, shadowing of a
+    /// structure is rejected later, when a [`CesFile`] is compiled.
+    ///
+    /// [`CesFile`]: crate::CesFile
+    pub(crate) fn with_bindings(self, bindings: Vec<(CesName, Rex)>) -> Self {
+        if bindings.is_empty() {
+            return self
+        }
+
+        let mut env = BTreeMap::new();
+        let mut used = BTreeSet::new();
+
+        for (name, rex) in bindings.into_iter() {
+            let rex = rex.spliced_clone(&env, &mut used);
+
+            if env.insert(name.clone(), rex).is_some() && !used.contains(&name) {
+                warn!("Let binding '{}' shadowed before use", name);
+            }
+        }
+
+        let result = self.spliced_clone(&env, &mut used);
+
+        for name in env.keys() {
+            if !used.contains(name) {
+                warn!("Unused let binding '{}'", name);
+            }
+        }
+
+        result
+    }
+
+    fn spliced_clone(&self, env: &BTreeMap<CesName, Rex>, used: &mut BTreeSet<CesName>) -> Self {
+        let mut kinds = Vec::new();
+
+        if !self.kinds.is_empty() {
+            self.splice_into(0, env, used, &mut kinds);
+        }
+
+        Rex { kinds }
+    }
+
+    /// Appends a copy of the subtree rooted at `id` to `kinds`, with
+    /// references to bindings in `env` replaced by bound expressions.
+    /// Returns the position of the subtree's root in `kinds`.
+    fn splice_into(
+        &self,
+        id: RexID,
+        env: &BTreeMap<CesName, Rex>,
+        used: &mut BTreeSet<CesName>,
+        kinds: &mut Vec<RexKind>,
+    ) -> RexID {
+        let pos = kinds.len();
+
+        match &self.kinds[id] {
+            RexKind::Immediate(imm) if env.contains_key(&imm.name) => {
+                used.insert(imm.name.clone());
+                kinds.append_with_offset(env[&imm.name].kinds.clone(), pos);
+            }
+            RexKind::Product(tree) => {
+                kinds.push(RexKind::Product(RexTree::default()));
+                let ids = tree.ids.iter().map(|&i| self.splice_into(i, env, used, kinds)).collect();
+                kinds[pos] = RexKind::Product(RexTree { ids });
+            }
+            RexKind::Sum(tree) => {
+                kinds.push(RexKind::Sum(RexTree::default()));
+                let ids = tree.ids.iter().map(|&i| self.splice_into(i, env, used, kinds)).collect();
+                kinds[pos] = RexKind::Sum(RexTree { ids });
+            }
+            kind => kinds.push(kind.clone()),
+        }
+
+        pos
+    }
+
+    /// Appends to `result` the provenance of every entry between dots
+    /// `cause` and `effect` contributed by an arrow rule of this
+    /// `Rex`, which is the definition of the last structure in
+    /// `chain`.
+    pub(crate) fn explain_into(
+        &self,
+        cause: &DotName,
+        effect: &DotName,
+        chain: &[(CesName, Span)],
+        result: &mut Vec<Provenance>,
+    ) {
+        for kind in self.kinds.iter() {
+            match kind {
+                RexKind::Thin(tar) => {
+                    for polarity in tar.get_polarities(cause, effect) {
+                        let provenance =
+                            Provenance::new(chain.to_vec(), tar.clone(), polarity, Vec::new());
+
+                        result.push(provenance);
+                    }
+                }
+                RexKind::Fat(far) => {
+                    let parts = far.get_thinned_parts();
+                    let is_split = parts.len() > 1;
+
+                    for (ndx, (tx_tar, rx_tar)) in parts.into_iter().enumerate() {
+                        let fit_steps = if is_split {
+                            vec![FitStep::Split(ndx), FitStep::Thinning]
+                        } else {
+                            vec![FitStep::Thinning]
+                        };
+
+                        for tar in Some(tx_tar).into_iter().chain(Some(rx_tar)) {
+                            for polarity in tar.get_polarities(cause, effect) {
+                                result.push(Provenance::new(
+                                    chain.to_vec(),
+                                    tar.clone(),
+                                    polarity,
+                                    fit_steps.clone(),
+                                ));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Returns names of all structures instantiated in this `Rex`,
+    /// along with locations of instantiations, in order of
+    /// occurrence.
+    pub(crate) fn get_instantiations(&self) -> Vec<(CesName, Span)> {
+        self.kinds
+            .iter()
+            .filter_map(|kind| match kind {
+                RexKind::Immediate(imm) => Some((imm.name.clone(), imm.span)),
+                RexKind::Instance(instance) => Some((instance.name.clone(), instance.span)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the ids of operands, if the node `id` is a product or
+    /// a sum, or an empty slice otherwise.
+    pub(crate) fn get_children(&self, id: RexID) -> &[RexID] {
+        match &self.kinds[id] {
+            RexKind::Product(tree) | RexKind::Sum(tree) => tree.as_slice(),
+            _ => &[],
+        }
+    }
+
+    /// Returns a copy of the subexpression rooted at node `id`.
+    pub(crate) fn get_subtree(&self, id: RexID) -> Self {
+        let mut kinds = Vec::new();
+
+        self.splice_into(id, &BTreeMap::new(), &mut BTreeSet::new(), &mut kinds);
+
+        Rex { kinds }
+    }
+
+    fn fmt_node(&self, id: RexID, within: Option<bool>, f: &mut fmt::Formatter) -> fmt::Result {
+        // `within` is `None` at the root, `Some(true)` inside a
+        // product, and `Some(false)` inside a sum.
+        match &self.kinds[id] {
+            RexKind::Thin(tar) if within.is_some() => write!(f, "{{ {} }}", tar),
+            RexKind::Thin(tar) => write!(f, "{}", tar),
+            RexKind::Fat(far) if within.is_some() => write!(f, "{{ {} }}", far),
+            RexKind::Fat(far) => write!(f, "{}", far),
+            RexKind::Immediate(imm) => write!(f, "{}", imm),
+            RexKind::Instance(instance) => write!(f, "{}", instance),
+            RexKind::Product(tree) => {
+                if within == Some(true) {
+                    f.write_str("{ ")?;
+                }
+
+                for (ndx, &child) in tree.ids.iter().enumerate() {
+                    if ndx > 0 {
+                        f.write_str(" ")?;
+                    }
+                    self.fmt_node(child, Some(true), f)?;
+                }
+
+                if within == Some(true) {
+                    f.write_str(" }")?;
+                }
+
+                Ok(())
+            }
+            RexKind::Sum(tree) => {
+                if within.is_some() {
+                    f.write_str("{ ")?;
+                }
+
+                for (ndx, &child) in tree.ids.iter().enumerate() {
+                    if ndx > 0 {
+                        f.write_str(" + ")?;
+                    }
+                    self.fmt_node(child, Some(false), f)?;
+                }
+
+                if within.is_some() {
+                    f.write_str(" }")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+
     /// Returns a copy of this `Rex` converted to the normal form.
     // FIXME the result of FIT transformation should be further
     // simplified.
@@ -163,6 +380,114 @@ impl Rex {
 
         Rex { kinds: new_kinds }
     }
+
+    /// Returns the set of all [`DotName`]s occurring in arrow rules
+    /// of this `Rex`, not including dots of instantiated structures.
+    pub fn get_dot_names(&self) -> BTreeSet<DotName> {
+        let mut result = BTreeSet::new();
+
+        for kind in self.kinds.iter() {
+            match kind {
+                RexKind::Thin(tar) => {
+                    result.extend(tar.dots.dot_names.iter().cloned());
+                    result.extend(tar.cause.get_dot_names());
+                    result.extend(tar.effect.get_dot_names());
+                }
+                RexKind::Fat(far) => {
+                    for part in far.parts.iter() {
+                        result.extend(part.cause.get_dot_names());
+                        result.extend(part.effect.get_dot_names());
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        result
+    }
+
+    /// Returns all thin arrow rules of the normal form of this `Rex`
+    /// which match a `pattern` given in textual form.
+    ///
+    /// See [`RexPattern`] for pattern syntax.  Every returned rule
+    /// carries the [`Span`] of the arrow rule it originated from.
+    pub fn find<S: AsRef<str>>(&self, pattern: S) -> Result<Vec<ThinArrowRule>, AscesisError> {
+        let pattern: RexPattern = pattern.as_ref().parse()?;
+
+        Ok(self.find_matching(&pattern))
+    }
+
+    /// Returns all thin arrow rules of the normal form of this `Rex`
+    /// which match a `pattern`.
+    pub fn find_matching(&self, pattern: &RexPattern) -> Vec<ThinArrowRule> {
+        self.fit_clone()
+            .kinds
+            .into_iter()
+            .filter_map(|kind| match kind {
+                RexKind::Thin(tar) if pattern.matches(&tar) => Some(tar),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns [`Span`]s of all instantiations of the structure
+    /// named `ces_name`, both immediate and templated, occurring in
+    /// this `Rex`.
+    pub fn find_instances_of<S: AsRef<str>>(&self, ces_name: S) -> Vec<Span> {
+        let ces_name = ces_name.to_ces_name();
+
+        self.kinds
+            .iter()
+            .filter_map(|kind| match kind {
+                RexKind::Immediate(imm) if imm.name == ces_name => Some(imm.span),
+                RexKind::Instance(inst) if inst.name == ces_name => Some(inst.span),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns names of all structures instantiated in this `Rex`.
+    pub fn get_instance_names(&self) -> BTreeSet<CesName> {
+        self.kinds
+            .iter()
+            .filter_map(|kind| match kind {
+                RexKind::Immediate(imm) => Some(imm.name.clone()),
+                RexKind::Instance(inst) => Some(inst.name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Replaces all references to polynomial abbreviations in arrow
+    /// rules with their expansions.
+    ///
+    /// Returns an error if an abbreviation referenced in the dot list
+    /// of a thin arrow rule doesn't expand to a single monomial, or if
+    /// an abbreviation is repeated in a product, see
+    /// [`PolyAbbreviations::apply`].
+    pub(crate) fn expand_abbreviations(
+        &mut self,
+        abbrevs: &PolyAbbreviations,
+    ) -> Result<(), AscesisError> {
+        for kind in self.kinds.iter_mut() {
+            match kind {
+                RexKind::Thin(tar) => {
+                    abbrevs.apply_to_dots(&mut tar.dots)?;
+                    abbrevs.apply(&mut tar.cause)?;
+                    abbrevs.apply(&mut tar.effect)?;
+                }
+                RexKind::Fat(far) => {
+                    for part in far.parts.iter_mut() {
+                        abbrevs.apply(&mut part.cause)?;
+                        abbrevs.apply(&mut part.effect)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl CompilableAsContent for Rex {
@@ -276,6 +601,16 @@ impl CompilableAsContent for Rex {
     }
 }
 
+impl fmt::Display for Rex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.kinds.is_empty() {
+            Ok(())
+        } else {
+            self.fmt_node(0, None, f)
+        }
+    }
+}
+
 impl From<ThinArrowRule> for Rex {
     fn from(rule: ThinArrowRule) -> Self {
         Rex { kinds: vec![RexKind::Thin(rule)] }
@@ -330,11 +665,12 @@ impl AppendWithOffset for Vec<RexKind> {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct ThinArrowRule {
     dots:   DotList,
     cause:  Polynomial,
     effect: Polynomial,
+    span:   Span,
 }
 
 impl ThinArrowRule {
@@ -357,9 +693,79 @@ impl ThinArrowRule {
         self
     }
 
+    pub(crate) fn with_span(mut self, start: usize, end: usize) -> Self {
+        self.span = Span::new(start, end);
+        self
+    }
+
     pub fn get_dots(&self) -> &[DotName] {
         &self.dots.dot_names
     }
+
+    pub fn get_cause(&self) -> &Polynomial {
+        &self.cause
+    }
+
+    pub fn get_effect(&self) -> &Polynomial {
+        &self.effect
+    }
+
+    /// Returns the location of this rule in the script it was parsed
+    /// from.  A rule obtained by FIT transformation inherits the
+    /// location of its fat arrow rule.
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+}
+
+impl ThinArrowRule {
+    /// Returns the polarities, in which this rule contributes an
+    /// entry between dots `cause` and `effect`: `Tx`, if `cause` is
+    /// in the dot list and `effect` occurs in the effect polynomial,
+    /// and `Rx`, if `effect` is in the dot list and `cause` occurs in
+    /// the cause polynomial.
+    pub(crate) fn get_polarities(&self, cause: &DotName, effect: &DotName) -> Vec<Polarity> {
+        let mut result = Vec::new();
+
+        if self.dots.dot_names.contains(cause)
+            && self.effect.monomials.iter().any(|mono| mono.contains(effect))
+        {
+            result.push(Polarity::Tx);
+        }
+
+        if self.dots.dot_names.contains(effect)
+            && self.cause.monomials.iter().any(|mono| mono.contains(cause))
+        {
+            result.push(Polarity::Rx);
+        }
+
+        result
+    }
+}
+
+impl PartialEq for ThinArrowRule {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.dots == other.dots && self.cause == other.cause && self.effect == other.effect
+    }
+}
+
+impl Eq for ThinArrowRule {}
+
+impl fmt::Display for ThinArrowRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.dots.fmt(f)?;
+
+        if !self.cause.monomials.is_empty() {
+            write!(f, " <- {}", self.cause)?;
+        }
+
+        if !self.effect.monomials.is_empty() {
+            write!(f, " -> {}", self.effect)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl CompilableAsContent for ThinArrowRule {
@@ -412,9 +818,10 @@ struct FatArrow {
     effect: Polynomial,
 }
 
-#[derive(Clone, PartialEq, Eq, Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct FatArrowRule {
     parts: Vec<FatArrow>,
+    span:  Span,
 }
 
 impl FatArrowRule {
@@ -442,6 +849,68 @@ impl FatArrowRule {
         }
         far
     }
+
+    pub(crate) fn with_span(mut self, start: usize, end: usize) -> Self {
+        self.span = Span::new(start, end);
+        self
+    }
+
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
+
+    /// Returns the result of step 1. of FIT transformation, i.e. an
+    /// effect-only and a cause-only thin arrow rule for each
+    /// two-polynomial part of this rule.
+    pub(crate) fn get_thinned_parts(&self) -> Vec<(ThinArrowRule, ThinArrowRule)> {
+        self.parts
+            .iter()
+            .map(|part| {
+                let sources = part.cause.flattened_clone();
+                let sinks = part.effect.flattened_clone();
+
+                let mut tx_tar = ThinArrowRule::new()
+                    .with_dots(sources)
+                    .unwrap()
+                    .with_effect(part.effect.clone());
+                let mut rx_tar =
+                    ThinArrowRule::new().with_dots(sinks).unwrap().with_cause(part.cause.clone());
+
+                tx_tar.span = self.span;
+                rx_tar.span = self.span;
+
+                (tx_tar, rx_tar)
+            })
+            .collect()
+    }
+}
+
+impl PartialEq for FatArrowRule {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.parts == other.parts
+    }
+}
+
+impl Eq for FatArrowRule {}
+
+/// A rule of more than two polynomials is displayed as a sum of its
+/// two-polynomial parts.
+impl fmt::Display for FatArrowRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let [part] = self.parts.as_slice() {
+            write!(f, "{} => {}", part.cause, part.effect)
+        } else {
+            for (ndx, part) in self.parts.iter().enumerate() {
+                if ndx > 0 {
+                    f.write_str(" + ")?;
+                }
+                write!(f, "{{ {} => {} }}", part.cause, part.effect)?;
+            }
+
+            Ok(())
+        }
+    }
 }
 
 impl From<FatArrowRule> for Vec<ThinArrowRule> {
@@ -465,20 +934,10 @@ impl From<&FatArrowRule> for Vec<ThinArrowRule> {
         // replaced with a sum of two thin arrow rules, one
         // effect-only, another cause-only.
 
-        let mut tx_tars = Vec::new();
-        let mut rx_tars = Vec::new();
+        // Step 1. is done in FatArrowRule::get_thinned_parts().
 
-        for part in far.parts.iter() {
-            let sources = part.cause.flattened_clone();
-            let sinks = part.effect.flattened_clone();
-
-            tx_tars.push(
-                ThinArrowRule::new().with_dots(sources).unwrap().with_effect(part.effect.clone()),
-            );
-            rx_tars.push(
-                ThinArrowRule::new().with_dots(sinks).unwrap().with_cause(part.cause.clone()),
-            );
-        }
+        let (mut tx_tars, mut rx_tars): (Vec<_>, Vec<_>) =
+            far.get_thinned_parts().into_iter().unzip();
 
         loop {
             let mut at_fixpoint = true;
@@ -578,7 +1037,9 @@ impl From<&FatArrowRule> for Vec<ThinArrowRule> {
 
 #[cfg(test)]
 mod tests {
-    use crate::ToCesName;
+    use std::iter::FromIterator;
+    use aces::CompilableMut;
+    use crate::{ToCesName, ToDotName, ImmediateDef, CesFile, golden};
     use super::*;
 
     #[test]
@@ -604,17 +1065,23 @@ mod tests {
                                 effect: Polynomial::from("b"),
                             }
                         ],
+                        ..Default::default()
                     }),
                     RexKind::Sum(RexTree { ids: vec![4, 5] }),
-                    RexKind::Instance(CesInstance { name: "d".to_ces_name(), args: vec![] }),
+                    RexKind::Instance(CesInstance {
+                        name: "d".to_ces_name(),
+                        ..Default::default()
+                    }),
                     RexKind::Product(RexTree { ids: vec![6, 7] }),
                     RexKind::Instance(CesInstance {
                         name: "e".to_ces_name(),
                         args: vec!["f".to_string()],
+                        ..Default::default()
                     }),
                     RexKind::Instance(CesInstance {
                         name: "g".to_ces_name(),
                         args: vec!["h".to_string(), "i".to_string()],
+                        ..Default::default()
                     }),
                     RexKind::Product(RexTree { ids: vec![9, 13] }),
                     RexKind::Product(RexTree { ids: vec![10, 11, 12] }),
@@ -622,18 +1089,24 @@ mod tests {
                         dots:   DotList::from(vec!["k"]),
                         cause:  Polynomial::from("j"),
                         effect: Polynomial::from("l"),
+                        ..Default::default()
                     }),
                     RexKind::Thin(ThinArrowRule {
                         dots:   DotList::from(vec!["j"]),
                         cause:  Polynomial::default(),
                         effect: Polynomial::from("k"),
+                        ..Default::default()
                     }),
                     RexKind::Thin(ThinArrowRule {
                         dots:   DotList::from(vec!["l"]),
                         cause:  Polynomial::from("k"),
                         effect: Polynomial::default(),
+                        ..Default::default()
+                    }),
+                    RexKind::Immediate(CesImmediate {
+                        name: "m".to_ces_name(),
+                        ..Default::default()
                     }),
-                    RexKind::Immediate(CesImmediate { name: "m".to_ces_name() }),
                 ],
             }
         );
@@ -652,6 +1125,7 @@ mod tests {
                         cause:  Polynomial::from("a"),
                         effect: Polynomial::from("b"),
                     },],
+                    ..Default::default()
                 }),],
             }
         );
@@ -667,11 +1141,13 @@ mod tests {
                         dots:   DotList::from(vec!["a"]),
                         cause:  Polynomial::default(),
                         effect: Polynomial::from("b"),
+                        ..Default::default()
                     }),
                     RexKind::Thin(ThinArrowRule {
                         dots:   DotList::from(vec!["b"]),
                         cause:  Polynomial::from("a"),
                         effect: Polynomial::default(),
+                        ..Default::default()
                     }),
                 ],
             }
@@ -693,16 +1169,19 @@ mod tests {
                         dots:   DotList::from(vec!["a"]),
                         cause:  Polynomial::default(),
                         effect: Polynomial::from("b"),
+                        ..Default::default()
                     }),
                     RexKind::Thin(ThinArrowRule {
                         dots:   DotList::from(vec!["b"]),
                         cause:  Polynomial::from("a"),
                         effect: Polynomial::from("c"),
+                        ..Default::default()
                     }),
                     RexKind::Thin(ThinArrowRule {
                         dots:   DotList::from(vec!["c"]),
                         cause:  Polynomial::from("b"),
                         effect: Polynomial::default(),
+                        ..Default::default()
                     }),
                 ],
             }
@@ -724,14 +1203,123 @@ mod tests {
                         dots:   DotList::from(vec!["b"]),
                         cause:  Polynomial::default(),
                         effect: Polynomial::from(vec![vec!["a"], vec!["c"]]),
+                        ..Default::default()
                     }),
                     RexKind::Thin(ThinArrowRule {
                         dots:   DotList::from(vec!["a", "c"]),
                         cause:  Polynomial::from("b"),
                         effect: Polynomial::default(),
+                        ..Default::default()
                     }),
                 ],
             }
         );
     }
+
+    #[test]
+    fn test_rex_dot_names() {
+        let phrase = "{ a => b <= c } { d -> e f + g } + h()";
+        let rex: Rex = phrase.parse().unwrap();
+        let dot_names = ["a", "b", "c", "d", "e", "f", "g"];
+
+        assert_eq!(rex.get_dot_names(), BTreeSet::from_iter(dot_names.iter().map(|n| n.to_dot())));
+    }
+
+    #[test]
+    fn test_rex_find() {
+        let phrase = "{ a => b } { c -> b } + { d -> b + e } F!(x) + F()";
+        let rex: Rex = phrase.parse().unwrap();
+
+        let found = rex.find("_ -> b").unwrap();
+        let found: Vec<String> = found.iter().map(|tar| tar.to_string()).collect();
+        assert_eq!(found, ["a -> b", "c -> b"]);
+
+        let found = rex.find("_ -> b + _").unwrap();
+        assert_eq!(found.len(), 3);
+        assert_eq!(found[2].to_string(), "d -> b + e");
+
+        let found = rex.find("b <- _").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].get_span().get_text(phrase), Some("a => b"));
+
+        let found = rex.find("c -> b").unwrap();
+        assert_eq!(found[0].get_span().get_text(phrase), Some("c -> b"));
+
+        assert!(rex.find("a -> c").unwrap().is_empty());
+        assert!(rex.find("a -> ").is_err());
+
+        let spans = rex.find_instances_of("F");
+        let texts: Vec<_> = spans.iter().map(|span| span.get_text(phrase).unwrap()).collect();
+        assert_eq!(texts, ["F!(x)", "F()"]);
+        assert_ne!(spans[0], spans[1]);
+    }
+
+    #[test]
+    fn test_rex_spans_ignored() {
+        let phrase = "{ a -> b } { c => d } F!(x) G()";
+        let rex: Rex = phrase.parse().unwrap();
+        let shifted: Rex = format!("   {}", phrase).parse().unwrap();
+
+        assert_eq!(rex, shifted);
+
+        let tar = &rex.find("a -> b").unwrap()[0];
+        let shifted_tar = &shifted.find("a -> b").unwrap()[0];
+
+        assert_eq!(tar, shifted_tar);
+        assert_ne!(tar.get_span(), shifted_tar.get_span());
+        assert_ne!(rex.find_instances_of("G"), shifted.find_instances_of("G"));
+    }
+
+    #[test]
+    fn test_instance_args() {
+        let rex: Rex = "F!(a, b,) + G!(\n    c\n    d\n) + H!()".parse().unwrap();
+        let args: Vec<Vec<String>> = rex
+            .kinds
+            .iter()
+            .filter_map(|kind| match kind {
+                RexKind::Instance(instance) => Some(instance.args.clone()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(args, [vec!["a", "b"], vec!["c", "d"], vec![]]);
+
+        let err = "{ a -> b } F!(a,, b)".parse::<Rex>().unwrap_err();
+
+        assert_eq!(
+            AscesisErrorKind::from(err).to_string(),
+            "Empty argument #2 of instance 'F' at [1:17]"
+        );
+    }
+
+    #[test]
+    fn test_let_bindings() {
+        let phrase = "ces m { let r = { a -> b + c }; let s = r() { d -> e }; r() s() + x() }";
+        let def: ImmediateDef = phrase.parse().unwrap();
+        let rex: Rex = "{ a -> b + c } { { a -> b + c } { d -> e } } + x()".parse().unwrap();
+
+        assert_eq!(def.get_rex(), &rex);
+    }
+
+    #[test]
+    fn test_let_binding_shadowing() {
+        let script = "ces r { x -> y }\nces Main { let r = { a -> b }; r() }";
+        let err = golden::compile_script(script, "Main").unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Let binding 'r' in 'Main' shadows a structure of the same name"
+        );
+
+        let (ctx, _) = golden::compile_script("ces r { x -> y }", "r").unwrap();
+        let mut ces_file = CesFile::from_script("ces Main { let r = { a -> b }; r() }").unwrap();
+
+        ces_file.set_root_name("Main").unwrap();
+        let err = ces_file.compile_mut(&ctx).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Let binding 'r' in 'Main' shadows a structure of the same name"
+        );
+    }
 }
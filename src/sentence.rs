@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 use crate::grammar::{Grammar, SymbolID, ProductionID};
 
 #[derive(Default, Debug)]
@@ -55,13 +58,20 @@ impl Default for ProductionUsed {
     }
 }
 
-/// Axiom-independent derivation data.
+/// Axiom-independent derivation data, together with a cache of
+/// axiom-specific derivation data.
+///
+/// Axiom-specific data is computed once per axiom, on first request,
+/// and then shared by all [`RootedGenerator`]s of that axiom.  A
+/// `Generator` may be shared between threads, hence rooted generators
+/// of different axioms may be created and used concurrently.
 #[derive(Debug)]
 pub struct Generator<'g> {
     grammar:    &'g Grammar,
     symbol_min: HashMap<SymbolID, Option<usize>>, // symbol -> shortest length
     prod_min:   Vec<Option<usize>>,               // production index -> shortest length
     best_prod:  HashMap<SymbolID, Option<usize>>, // nonterminal -> production index
+    rooted:     Mutex<HashMap<SymbolID, Arc<AxiomData>>>, // axiom -> derivation data
 }
 
 impl<'g> Generator<'g> {
@@ -123,41 +133,66 @@ impl<'g> Generator<'g> {
             }
         }
 
-        Self { grammar, symbol_min, prod_min, best_prod }
+        let rooted = Mutex::new(HashMap::new());
+
+        Self { grammar, symbol_min, prod_min, best_prod, rooted }
     }
 
-    /// Returns a new `RootedGenerator` and gathers axiom-specific
-    /// derivation data.
+    /// Returns a new `RootedGenerator` of `axiom`.
     ///
-    /// Computes shortest derivation paths from `axiom` through all
-    /// nonterminals.  For each nonterminal stores the computed length
-    /// and the ID of the best production having that nonterminal on
-    /// the right (best parent production).
-    pub fn rooted<S: AsRef<str>>(&self, axiom: S) -> Result<RootedGenerator, String> {
-        RootedGenerator::new(self, axiom)
+    /// Axiom-specific derivation data is gathered only if this is the
+    /// first request for `axiom`, otherwise cached data is reused.
+    pub fn rooted<S: AsRef<str>>(&self, axiom: S) -> Result<RootedGenerator<'_, 'g>, String> {
+        let axiom = axiom.as_ref();
+        let axiom_id = {
+            if let Some(id) = self.grammar.id_of_nonterminal(axiom) {
+                id
+            } else {
+                return Err(format!("No such nonterminal: <{}>", axiom))
+            }
+        };
+
+        let cached = self.rooted.lock().unwrap().get(&axiom_id).cloned();
+
+        if let Some(data) = cached {
+            return Ok(RootedGenerator { base: self, data })
+        }
+
+        // Derivation data is gathered without holding the lock, so
+        // that other axioms aren't blocked.  If another thread gets
+        // ahead with the same axiom, its data is kept and ours is
+        // dropped.
+        let data = Arc::new(AxiomData::new(self, axiom_id));
+        let data = self.rooted.lock().unwrap().entry(axiom_id).or_insert(data).clone();
+
+        Ok(RootedGenerator { base: self, data })
+    }
+
+    /// Returns `true` if axiom-specific derivation data of `axiom` is
+    /// already cached.
+    pub fn is_rooted_at<S: AsRef<str>>(&self, axiom: S) -> bool {
+        if let Some(axiom_id) = self.grammar.id_of_nonterminal(axiom.as_ref()) {
+            self.rooted.lock().unwrap().contains_key(&axiom_id)
+        } else {
+            false
+        }
     }
 }
 
 /// Axiom-specific derivation data.
 #[derive(Debug)]
-pub struct RootedGenerator<'b, 'g: 'b> {
-    base:        &'b Generator<'g>,
+struct AxiomData {
     axiom_id:    SymbolID,
     min_through: HashMap<SymbolID, Option<usize>>, // nonterminal -> shortest length
     best_parent: HashMap<SymbolID, Option<usize>>, // nonterminal -> production index
 }
 
-impl<'b, 'g: 'b> RootedGenerator<'b, 'g> {
-    fn new<S: AsRef<str>>(base: &'b Generator<'g>, axiom: S) -> Result<Self, String> {
-        let axiom = axiom.as_ref();
-        let axiom_id = {
-            if let Some(id) = base.grammar.id_of_nonterminal(axiom) {
-                id
-            } else {
-                return Err(format!("No such nonterminal: <{}>", axiom))
-            }
-        };
-
+impl AxiomData {
+    /// Computes shortest derivation paths from the axiom through all
+    /// nonterminals.  For each nonterminal stores the computed length
+    /// and the ID of the best production having that nonterminal on
+    /// the right (best parent production).
+    fn new(base: &Generator, axiom_id: SymbolID) -> Self {
         let mut min_through = HashMap::new();
         let mut best_parent = HashMap::new();
 
@@ -193,7 +228,24 @@ impl<'b, 'g: 'b> RootedGenerator<'b, 'g> {
             }
         }
 
-        Ok(Self { base, axiom_id, min_through, best_parent })
+        Self { axiom_id, min_through, best_parent }
+    }
+}
+
+/// A [`Generator`] rooted at an axiom.
+///
+/// Rooted generators are cheap to clone, since axiom-specific
+/// derivation data is shared.  Each call to [`RootedGenerator::iter`]
+/// returns an independent [`Emitter`] with its own emission state.
+#[derive(Clone, Debug)]
+pub struct RootedGenerator<'b, 'g: 'b> {
+    base: &'b Generator<'g>,
+    data: Arc<AxiomData>,
+}
+
+impl<'b, 'g: 'b> RootedGenerator<'b, 'g> {
+    pub fn get_axiom(&self) -> &str {
+        self.base.grammar.get_nonterminal(self.data.axiom_id).unwrap()
     }
 
     pub fn iter<'r>(&'r self) -> Emitter<'r, 'b, 'g> {
@@ -289,7 +341,7 @@ impl Iterator for Emitter<'_, '_, '_> {
     fn next(&mut self) -> Option<Self::Item> {
         let generator = self.generator.take().unwrap();
         let grammar = generator.base.grammar;
-        let axiom_id = generator.axiom_id;
+        let axiom_id = generator.data.axiom_id;
 
         self.out_sentence.clear();
         self.on_stack.insert(axiom_id, 1);
@@ -330,7 +382,9 @@ impl Iterator for Emitter<'_, '_, '_> {
                         if let ProductionUsed::ID(_) = self.which_prod[&child_nt_id] {
                             let mut parent_nt_id = child_nt_id;
 
-                            while let Some(best_prod_id) = generator.best_parent[&parent_nt_id] {
+                            let best_parent = &generator.data.best_parent;
+
+                            while let Some(best_prod_id) = best_parent[&parent_nt_id] {
                                 parent_nt_id = grammar.get(best_prod_id).unwrap().lhs();
 
                                 if let ProductionUsed::ID(_) = self.which_prod[&parent_nt_id] {
@@ -390,3 +444,57 @@ impl Iterator for Emitter<'_, '_, '_> {
         Some(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use super::*;
+
+    #[test]
+    fn test_rooted_cache() {
+        let grammar = Grammar::of_ascesis();
+        let generator = Generator::new(&grammar);
+
+        assert!(!generator.is_rooted_at("Rex"));
+
+        let first = generator.rooted("Rex").unwrap();
+
+        assert!(generator.is_rooted_at("Rex"));
+        assert!(!generator.is_rooted_at("CesFile"));
+
+        let second = generator.rooted("Rex").unwrap();
+
+        assert!(Arc::ptr_eq(&first.data, &second.data));
+        assert_eq!(second.get_axiom(), "Rex");
+        assert!(generator.rooted("NoSuchAxiom").is_err());
+    }
+
+    #[test]
+    fn test_rooted_concurrent() {
+        let grammar = Grammar::of_ascesis();
+        let generator = Generator::new(&grammar);
+        let axioms = ["Rex", "CesFile"];
+
+        let expected: Vec<Vec<String>> = axioms
+            .iter()
+            .map(|axiom| Generator::new(&grammar).rooted(axiom).unwrap().iter().take(8).collect())
+            .collect();
+
+        let actual: Vec<Vec<String>> = thread::scope(|scope| {
+            let handles: Vec<_> = axioms
+                .iter()
+                .map(|axiom| {
+                    let generator = &generator;
+
+                    scope.spawn(move || generator.rooted(axiom).unwrap().iter().take(8).collect())
+                })
+                .collect();
+
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+
+        assert!(expected.iter().all(|sentences| !sentences.is_empty()));
+        assert_eq!(actual, expected);
+        assert!(axioms.iter().all(|axiom| generator.is_rooted_at(axiom)));
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::{BTreeMap, BTreeSet, btree_map};
+use aces::ContextHandle;
+use crate::{CesName, ToCesName, DotName, ImmediateDef};
+
+/// Statistics of sharing dot names into a [`ContextHandle`] by
+/// structure definitions of a [`CesFile`].
+///
+/// Names of dots are interned in a context, hence structures compiled
+/// in a common context refer to the same node whenever they use the
+/// same name.  A _collision_ is a dot name used in a definition,
+/// which was already known to the context before compilation started.
+/// Collisions are harmless when they are intended, e.g. when a model
+/// is recompiled, but otherwise indicate an accidental capture of
+/// another model's node.
+///
+/// Only rule expressions of structure definitions are taken into
+/// account.  Dots named solely in property blocks of a file, e.g. in
+/// `caps`, `weights` or `inhibit` blocks, belong to no structure,
+/// hence they don't appear in footprints, and aren't checked for
+/// collisions.
+///
+/// [`CesFile`]: crate::CesFile
+#[derive(Clone, Default, Debug)]
+pub struct SharingStats {
+    shared:     BTreeSet<DotName>,
+    collisions: BTreeSet<DotName>,
+    footprints: BTreeMap<CesName, BTreeSet<DotName>>,
+}
+
+impl SharingStats {
+    /// Gathers node footprints of all `defs` and checks them against
+    /// dot names already known to `ctx`.
+    ///
+    /// Must be called before `defs` are compiled, otherwise all dot
+    /// names are reported as collisions.
+    pub(crate) fn gather<'a, I>(ctx: &ContextHandle, defs: I) -> Self
+    where
+        I: IntoIterator<Item = &'a ImmediateDef>,
+    {
+        let mut result = Self::default();
+
+        for def in defs {
+            let dot_names = def.get_dot_names();
+
+            result.shared.extend(dot_names.iter().cloned());
+            result.footprints.entry(def.get_name().clone()).or_default().extend(dot_names);
+        }
+
+        let ctx = ctx.lock().unwrap();
+
+        for dot_name in result.shared.iter() {
+            if ctx.get_dot_id(dot_name).is_some() {
+                result.collisions.insert(dot_name.clone());
+            }
+        }
+
+        result
+    }
+
+    /// Returns the number of distinct dot names shared into the
+    /// context.
+    #[inline]
+    pub fn num_shared(&self) -> usize {
+        self.shared.len()
+    }
+
+    /// Returns all distinct dot names shared into the context.
+    #[inline]
+    pub fn get_shared(&self) -> &BTreeSet<DotName> {
+        &self.shared
+    }
+
+    /// Returns dot names, which were known to the context before
+    /// compilation started.
+    #[inline]
+    pub fn get_collisions(&self) -> &BTreeSet<DotName> {
+        &self.collisions
+    }
+
+    #[inline]
+    pub fn has_collisions(&self) -> bool {
+        !self.collisions.is_empty()
+    }
+
+    /// Returns the set of dot names used by the structure definition
+    /// of a given name, or `None` if there is no such definition.
+    pub fn get_footprint<S: AsRef<str>>(&self, ces_name: S) -> Option<&BTreeSet<DotName>> {
+        self.footprints.get(&ces_name.to_ces_name())
+    }
+
+    /// Returns an iterator over footprints of all structure
+    /// definitions, ordered by structure name.
+    #[inline]
+    pub fn iter_footprints(&self) -> btree_map::Iter<'_, CesName, BTreeSet<DotName>> {
+        self.footprints.iter()
+    }
+
+    /// Returns names of all structure definitions using a given dot
+    /// name.
+    pub fn get_users_of<S: AsRef<str>>(&self, dot_name: S) -> Vec<&CesName> {
+        let dot_name = dot_name.as_ref();
+
+        self.footprints
+            .iter()
+            .filter(|(_, dots)| dots.iter().any(|dot| dot.as_ref() == dot_name))
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    pub fn log_collisions(&self) {
+        for dot_name in self.collisions.iter() {
+            let users: Vec<_> =
+                self.get_users_of(dot_name).into_iter().map(|n| n.as_str()).collect();
+
+            warn!(
+                "Dot '{}' used in {:?} was already known to the context",
+                dot_name.as_ref(),
+                users
+            );
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::{fs, path::PathBuf, error::Error};
+use ascesis::{
+    ContextHandle, PartialContent, Rex, golden,
+    differential::{self, RexPipeline, ReferencePipeline},
+};
+
+/// Compiles a rule expression after rewriting its textual form.
+struct RewritingPipeline {
+    name:    &'static str,
+    rewrite: fn(String) -> String,
+}
+
+impl RexPipeline for RewritingPipeline {
+    fn get_name(&self) -> &str {
+        self.name
+    }
+
+    fn compile_rex(
+        &self,
+        rex: &Rex,
+        ctx: &ContextHandle,
+    ) -> Result<PartialContent, Box<dyn Error>> {
+        let rex: Rex = (self.rewrite)(rex.to_string()).parse()?;
+
+        ReferencePipeline.compile_rex(&rex, ctx)
+    }
+}
+
+/// Round trip of every golden script through `Rex` display and
+/// parsing must not change compiled content.
+#[test]
+fn test_differential_roundtrip() {
+    let candidate = RewritingPipeline { name: "roundtrip", rewrite: |text| text };
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden");
+
+    for entry in fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.extension().and_then(|ext| ext.to_str()) == Some("ces") {
+            let script = fs::read_to_string(&path).unwrap();
+
+            differential::assert_equivalent(&ReferencePipeline, &candidate, &script, "Main");
+        }
+    }
+}
+
+#[test]
+fn test_differential_divergence() {
+    let candidate =
+        RewritingPipeline { name: "reversed", rewrite: |text| text.replace("=>", "<=") };
+    let script = "ces P { x -> y }\nces Main { P() { { a -> b } + { b => c d } } }";
+    let (ctx, ces_file) = golden::compile_script(script, "Main").unwrap();
+    let rex = ces_file.get_root().unwrap().get_rex();
+    let divergence = differential::compare(&ReferencePipeline, &candidate, rex, &ctx).unwrap_err();
+
+    assert_eq!(divergence.get_rex().to_string(), "b => c d");
+    assert_eq!(divergence.get_expected(), "b -> c d\nc <- b\nd <- b\n");
+    assert_eq!(divergence.get_actual(), "b <- c d\nc -> b\nd -> b\n");
+}
@@ -0,0 +1,29 @@
+use ascesis::{Polarity, FitStep, golden};
+
+#[test]
+fn test_explain() {
+    let script = "ces Fork { a => b c }\nces Main { { a -> b } Fork() + { x <= y => z } }";
+    let (_, ces_file) = golden::compile_script(script, "Main").unwrap();
+
+    let report: Vec<String> =
+        ces_file.explain("a", "b").unwrap().iter().map(|p| p.to_string()).collect();
+
+    assert_eq!(
+        report,
+        [
+            "a -> b (effect) at 35..41 in Main",
+            "a -> b c (effect) at 11..19 in Main > Fork, FIT: thinning",
+            "b c <- a (cause) at 11..19 in Main > Fork, FIT: thinning",
+        ]
+    );
+
+    let provenance = ces_file.explain("y", "z").unwrap();
+
+    assert_eq!(provenance.len(), 2);
+    assert_eq!(provenance[0].get_polarity(), Polarity::Tx);
+    assert_eq!(provenance[1].get_polarity(), Polarity::Rx);
+    assert_eq!(provenance[0].get_fit_steps(), [FitStep::Split(1), FitStep::Thinning]);
+    assert_eq!(provenance[0].get_span().get_text(script), Some("x <= y => z"));
+
+    assert!(ces_file.explain("b", "a").unwrap().is_empty());
+}
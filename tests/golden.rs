@@ -0,0 +1,46 @@
+use std::{fs, path::PathBuf};
+use ascesis::golden;
+
+/// Compiles every `*.ces` script in `tests/golden`, starting from its
+/// `Main` structure, and compares the result against the `*.snap`
+/// file of the same stem.
+#[test]
+fn test_golden_snapshots() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden");
+    let mut paths: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("ces"))
+        .collect();
+
+    paths.sort();
+    assert!(!paths.is_empty(), "No scripts in {}", dir.display());
+
+    let mut failures = Vec::new();
+
+    for path in paths {
+        let script = fs::read_to_string(&path).unwrap();
+
+        match golden::serialize_script(&script, "Main") {
+            Ok(actual) => {
+                if let Err(err) = golden::check_snapshot(path.with_extension("snap"), &actual) {
+                    failures.push(err.to_string());
+                }
+            }
+            Err(err) => failures.push(format!("{}: {}", path.display(), err)),
+        }
+    }
+
+    if !failures.is_empty() {
+        panic!("\n{}", failures.join("\n"))
+    }
+}
+
+#[test]
+fn test_golden_diff() {
+    assert_eq!(golden::diff("a -> b\n", "a -> b\n"), None);
+    assert_eq!(
+        golden::diff("a -> b\nb <- a\n", "a -> b + c\nb <- a\n").unwrap(),
+        "- a -> b\n+ a -> b + c\n  b <- a\n"
+    );
+}
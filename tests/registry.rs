@@ -0,0 +1,53 @@
+use std::{env, fs, process};
+use ascesis::{CesFile, registry::Registry};
+
+fn publish(registry: &Registry, script: &str) -> (u32, usize, usize) {
+    let mut ces_file = CesFile::from_script(script).unwrap();
+
+    ces_file.set_root_name("Main").unwrap();
+
+    let entry = registry.publish(&ces_file).unwrap();
+
+    (entry.get_version(), entry.get_added(), entry.get_removed())
+}
+
+#[test]
+fn test_registry_history() {
+    let root = env::temp_dir().join(format!("ascesis-registry-{}", process::id()));
+    let registry = Registry::open(&root).unwrap();
+
+    assert_eq!(publish(&registry, "ces Main { a => b }"), (1, 2, 0));
+    assert_eq!(publish(&registry, "ces Main { a => b + c }"), (2, 2, 1));
+    // Same content, different text.
+    assert_eq!(publish(&registry, "ces Main { { a => b + c } }"), (2, 2, 1));
+    // Same content, different capacities.
+    assert_eq!(publish(&registry, "ces Main { a => b + c }\ncaps { 2 a }"), (3, 1, 0));
+    assert_eq!(publish(&registry, "caps { 2 a }\nces Main { a => b + c }"), (3, 1, 0));
+    assert_eq!(publish(&registry, "caps { 3 a }\nces Main { a => b + c }"), (4, 1, 1));
+
+    let history = registry.history("Main").unwrap();
+
+    assert_eq!(history.len(), 4);
+    assert_ne!(history[0].get_fingerprint(), history[1].get_fingerprint());
+    assert_ne!(history[1].get_fingerprint(), history[2].get_fingerprint());
+    assert_ne!(history[2].get_fingerprint(), history[3].get_fingerprint());
+    assert_eq!(registry.get_names().unwrap(), ["Main"]);
+    assert_eq!(registry.get_script("Main", 1).unwrap(), "ces Main { a => b }");
+    assert_eq!(
+        registry.diff("Main", 1, 2).unwrap().unwrap(),
+        "- a -> b\n+ a -> b + c\n  b <- a\n+ c <- a\n"
+    );
+    assert_eq!(
+        registry.diff("Main", 2, 3).unwrap().unwrap(),
+        "  a -> b + c\n  b <- a\n  c <- a\n+ caps { 2 a }\n"
+    );
+    assert_eq!(
+        registry.diff("Main", 3, 4).unwrap().unwrap(),
+        "  a -> b + c\n  b <- a\n  c <- a\n- caps { 2 a }\n+ caps { 3 a }\n"
+    );
+    assert!(registry.get_script("Main", 3).unwrap().ends_with("caps { 2 a }"));
+    assert!(registry.diff("Main", 1, 5).is_err());
+    assert!(registry.history("Other").is_err());
+
+    fs::remove_dir_all(&root).unwrap();
+}
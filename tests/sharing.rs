@@ -0,0 +1,41 @@
+use ascesis::{CesFile, Context, CompilableMut};
+
+fn names<'a, I, S>(names: I) -> Vec<&'a str>
+where
+    I: IntoIterator<Item = &'a S>,
+    S: AsRef<str> + 'a + ?Sized,
+{
+    names.into_iter().map(|name| name.as_ref()).collect()
+}
+
+/// Two files compiled into a common context share the nodes they
+/// both refer to by name.
+#[test]
+fn test_sharing_collisions() {
+    let ctx = Context::new_toplevel("test");
+    let mut first = CesFile::from_script("ces Main { a -> b }").unwrap();
+
+    first.set_root_name("Main").unwrap();
+    first.compile_mut(&ctx).unwrap();
+
+    assert!(!first.get_sharing_stats().unwrap().has_collisions());
+
+    let script = "ces Other { b -> c }\nces Main { Other() { c -> a + d } }\ncaps { 2 e }";
+    let mut second = CesFile::from_script(script).unwrap();
+
+    second.set_root_name("Main").unwrap();
+    second.compile_mut(&ctx).unwrap();
+
+    let stats = second.get_sharing_stats().unwrap();
+
+    assert_eq!(names(stats.get_shared()), ["a", "b", "c", "d"]);
+    assert_eq!(names(stats.get_collisions()), ["a", "b"]);
+    assert_eq!(names(stats.get_footprint("Main").unwrap()), ["a", "c", "d"]);
+    assert_eq!(names(stats.get_footprint("Other").unwrap()), ["b", "c"]);
+    assert!(stats.get_footprint("Missing").is_none());
+
+    let users: Vec<&str> = stats.get_users_of("c").into_iter().map(|n| n.as_str()).collect();
+
+    assert_eq!(users, ["Main", "Other"]);
+    assert!(stats.get_users_of("e").is_empty());
+}